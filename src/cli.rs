@@ -1,11 +1,23 @@
-use std::path::PathBuf;
+use std::{
+    env, fs,
+    path::{Path, PathBuf},
+};
 
-use clap::{crate_authors, crate_name, crate_version, AppSettings, Clap};
+use anyhow::Context;
+use chrono::NaiveDate;
+use clap::{crate_authors, crate_name, crate_version, AppSettings, ArgEnum, Clap};
+
+use planner::clock::Clock;
+
+use crate::config::Config;
 
 // NOTE: These 2 constants should be changed together.  Not worth the time to
 // fix.  Deal with it.
 const PLANNER_DIR: &str = ".planner";
 
+const PLANNER_DIR_ENV: &str = "PLANNER_DIR";
+const PLANNER_TODAY_ENV: &str = "PLANNER_TODAY";
+
 /// A plaintext planning tool for a particular kind of nerd.  
 #[derive(Clap, Debug)]
 #[clap(name = crate_name!())]
@@ -13,6 +25,17 @@ const PLANNER_DIR: &str = ".planner";
 #[clap(author = crate_authors!())]
 #[clap(setting = AppSettings::ColoredHelp)]
 pub struct Options {
+    /// The directory containing plan files.  Overrides `PLANNER_DIR` and the
+    /// config file.
+    #[clap(long, global = true)]
+    dir: Option<PathBuf>,
+    /// The workspace to use, as named in the config file.
+    #[clap(short, long, global = true)]
+    workspace: Option<String>,
+    /// Pretend that today is another date, as YYYY-MM-DD.  Overrides
+    /// `PLANNER_TODAY`.
+    #[clap(long, global = true, hidden = true)]
+    today: Option<NaiveDate>,
     #[clap(subcommand)]
     command: Option<Command>,
 }
@@ -22,15 +45,234 @@ pub enum Command {
     /// Edit the plan in Vim.
     Edit,
     /// Send the plan to STDOUT.
-    View,
+    View(ViewOptions),
+    /// Set up a plan directory, config file and starter template.
+    Init(InitOptions),
+    /// Show today's plan.
+    Today(TodayOptions),
+    /// Check plan files for mismatched dates and parse errors.
+    Doctor(DoctorOptions),
+    /// Restore the previous version of a plan.
+    Undo(DateOptions),
+    /// Restore the version of a plan that the last undo replaced.
+    Redo(DateOptions),
+    /// List the saved versions of a plan.
+    History(DateOptions),
+    /// Export the plans in a date range.
+    Export(ExportOptions),
+    /// Add a day's events from an iCalendar file to the planned schedule.
+    ImportIcs(ImportIcsOptions),
+    /// Render every plan into a static HTML site.
+    Publish(PublishOptions),
+    /// Import or export tasks in the todo.txt format.
+    Todotxt(TodotxtOptions),
+    /// Sync tasks with Taskwarrior's JSON import/export format.
+    Taskwarrior(TaskwarriorOptions),
+    /// Show the planned event in progress, and the next one.
+    Now(NowOptions),
+    /// Show the next planned event, with a countdown.
+    Next(NowOptions),
+}
+
+#[derive(Clap, Debug)]
+pub struct ViewOptions {
+    /// The output format.
+    #[clap(long, arg_enum, default_value = "text")]
+    pub format: Format,
+    /// Draw the schedule as a timeline of planned and actual events.
+    #[clap(long)]
+    pub timeline: bool,
+}
+
+/// An output format for plans.
+#[derive(ArgEnum, Debug, Clone, Copy, PartialEq)]
+pub enum Format {
+    /// Colored text for the terminal.
+    Text,
+    Markdown,
+    Json,
+    Yaml,
+}
+
+#[derive(Clap, Debug)]
+pub struct NowOptions {
+    /// Print a single line, or nothing, e.g. for a shell prompt.
+    #[clap(long)]
+    pub short: bool,
+}
+
+#[derive(Clap, Debug)]
+pub struct InitOptions {
+    /// A folder of existing `*.plan.md` files to import.
+    #[clap(long)]
+    pub import: Option<PathBuf>,
+}
+
+#[derive(Clap, Debug)]
+pub struct TodayOptions {
+    /// Show today's plans from every workspace.
+    #[clap(long)]
+    pub all: bool,
+}
+
+#[derive(Clap, Debug)]
+pub struct DoctorOptions {
+    /// Rename or rewrite broken plans, after confirmation.
+    #[clap(long)]
+    pub fix: bool,
+    /// Apply fixes without asking for confirmation.
+    #[clap(short, long)]
+    pub yes: bool,
+}
+
+#[derive(Clap, Debug)]
+pub struct DateOptions {
+    /// The date of the plan, as YYYY-MM-DD.  Defaults to today.
+    pub date: Option<NaiveDate>,
+}
+
+#[derive(Clap, Debug)]
+pub struct ExportOptions {
+    /// The export format.
+    #[clap(long, arg_enum)]
+    pub format: ExportFormat,
+    /// The first date to export, as YYYY-MM-DD.  Defaults to the oldest plan.
+    #[clap(long)]
+    pub from: Option<NaiveDate>,
+    /// The last date to export, as YYYY-MM-DD.  Defaults to the newest plan.
+    #[clap(long)]
+    pub to: Option<NaiveDate>,
+    /// The directory to write the exported files to.
+    #[clap(long, default_value = ".")]
+    pub out: PathBuf,
+    /// Include actual events, as well as planned ones, in calendar exports.
+    #[clap(long)]
+    pub actual: bool,
+}
+
+#[derive(Clap, Debug)]
+pub struct ImportIcsOptions {
+    /// The iCalendar (.ics) file to import.
+    pub file: PathBuf,
+    /// The date of the plan, as YYYY-MM-DD.  Defaults to today.
+    #[clap(long)]
+    pub date: Option<NaiveDate>,
+}
+
+#[derive(Clap, Debug)]
+pub struct PublishOptions {
+    /// The directory to write the site to.
+    #[clap(long, default_value = "site")]
+    pub out: PathBuf,
+}
+
+#[derive(Clap, Debug)]
+pub struct TodotxtOptions {
+    #[clap(subcommand)]
+    pub command: TodotxtCommand,
+}
+
+#[derive(Clap, Debug)]
+pub enum TodotxtCommand {
+    /// Add the tasks from a todo.txt file to a plan.
+    Import(TodotxtImportOptions),
+    /// Print the tasks of a plan in the todo.txt format.
+    Export(DateOptions),
+}
+
+#[derive(Clap, Debug)]
+pub struct TodotxtImportOptions {
+    /// The todo.txt file to import.
+    pub file: PathBuf,
+    /// The date of the plan, as YYYY-MM-DD.  Defaults to today.
+    #[clap(long)]
+    pub date: Option<NaiveDate>,
+}
+
+#[derive(Clap, Debug)]
+pub struct TaskwarriorOptions {
+    #[clap(subcommand)]
+    pub command: TaskwarriorCommand,
+}
+
+#[derive(Clap, Debug)]
+pub enum TaskwarriorCommand {
+    /// Add or update tasks from the output of `task export`.
+    Import(TaskwarriorImportOptions),
+    /// Print the open tasks of a plan, and the ones completed since they were
+    /// imported, for `task import`.
+    Export(DateOptions),
+}
+
+#[derive(Clap, Debug)]
+pub struct TaskwarriorImportOptions {
+    /// The JSON file to import, or `-` for STDIN.
+    pub file: PathBuf,
+    /// The date of the plan, as YYYY-MM-DD.  Defaults to today.
+    #[clap(long)]
+    pub date: Option<NaiveDate>,
+}
+
+/// A format for exporting plans.
+#[derive(ArgEnum, Debug, Clone, Copy, PartialEq)]
+pub enum ExportFormat {
+    /// `tasks.csv` and `events.csv`.
+    Csv,
+    /// `planner.ics`, an iCalendar file of the schedule.
+    Ics,
 }
 
 impl Options {
     /// Gets the directory containing plan files.
-    pub fn get_root_dir(&self) -> PathBuf {
-        let mut home = dirs::home_dir().expect("Could not open home directory.");
+    ///
+    /// The directory is taken from `--dir`, then `--workspace`, then
+    /// `PLANNER_DIR`, then the config file's default workspace and directory,
+    /// and finally defaults to `~/.planner`.
+    pub fn get_root_dir(&self, config: &Config) -> anyhow::Result<PathBuf> {
+        if let Some(dir) = &self.dir {
+            return Ok(dir.to_owned());
+        }
+
+        if let Some(workspace) = &self.workspace {
+            return Ok(config.workspace_dir(workspace)?.to_owned());
+        }
+
+        if let Some(dir) = env::var_os(PLANNER_DIR_ENV).filter(|d| !d.is_empty()) {
+            return Ok(PathBuf::from(dir));
+        }
+
+        if let Some(workspace) = &config.default_workspace {
+            return Ok(config.workspace_dir(workspace)?.to_owned());
+        }
+
+        if let Some(dir) = &config.dir {
+            return Ok(dir.to_owned());
+        }
+
+        let mut home = dirs::home_dir().context("Could not find the home directory.")?;
         home.push(PLANNER_DIR);
-        home
+        Ok(home)
+    }
+
+    /// Gets the clock, which is fixed to `--today` or `PLANNER_TODAY` if
+    /// either is set.
+    pub fn clock(&self) -> anyhow::Result<Clock> {
+        if let Some(today) = self.today {
+            return Ok(Clock::fixed(today));
+        }
+
+        match env::var(PLANNER_TODAY_ENV) {
+            Ok(today) if !today.is_empty() => {
+                let today = today.parse().with_context(|| {
+                    format!(
+                        "Invalid {} {:?}, expected YYYY-MM-DD",
+                        PLANNER_TODAY_ENV, today
+                    )
+                })?;
+                Ok(Clock::fixed(today))
+            }
+            _ => Ok(Clock::system()),
+        }
     }
 
     pub fn command(self) -> Command {
@@ -40,3 +282,20 @@ impl Options {
         }
     }
 }
+
+/// Checks that the plan directory exists and can be read.
+pub fn check_root_dir(dir: &Path) -> anyhow::Result<()> {
+    if !dir.exists() {
+        anyhow::bail!(
+            "Plan directory {:?} does not exist.  Run `planner init` to create it.",
+            dir
+        );
+    }
+
+    if !dir.is_dir() {
+        anyhow::bail!("Plan directory {:?} is not a directory.", dir);
+    }
+
+    fs::read_dir(dir).with_context(|| format!("Could not read plan directory {:?}", dir))?;
+    Ok(())
+}
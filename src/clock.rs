@@ -0,0 +1,91 @@
+use chrono::{Local, NaiveDate, NaiveDateTime, NaiveTime};
+
+/// The source of the current date and time.
+///
+/// Everything that depends on what day it is asks the clock, so that other
+/// days can be simulated with `--today` or `PLANNER_TODAY`.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Clock {
+    /// A fixed plan date, used instead of the system's date.
+    fixed: Option<NaiveDate>,
+    /// The time of day at which a new plan starts.
+    day_start: NaiveTime,
+}
+
+impl Clock {
+    /// A clock that follows the system's local time.
+    pub fn system() -> Self {
+        Clock {
+            fixed: None,
+            day_start: NaiveTime::from_hms(0, 0, 0),
+        }
+    }
+
+    /// A clock fixed to a plan date, with the system's local time of day.
+    pub fn fixed(date: NaiveDate) -> Self {
+        Clock {
+            fixed: Some(date),
+            ..Clock::system()
+        }
+    }
+
+    /// Sets the time of day at which a new plan starts.  Until then, times
+    /// belong to the previous day's plan.
+    pub fn with_day_start(mut self, day_start: NaiveTime) -> Self {
+        self.day_start = day_start;
+        self
+    }
+
+    /// Gets the time of day at which a new plan starts.
+    pub fn day_start(&self) -> NaiveTime {
+        self.day_start
+    }
+
+    /// Gets the current local date and time.
+    pub fn now(&self) -> NaiveDateTime {
+        let now = Local::now().naive_local();
+
+        match self.fixed {
+            None => now,
+            Some(date) if now.time() < self.day_start => date.succ().and_time(now.time()),
+            Some(date) => date.and_time(now.time()),
+        }
+    }
+
+    /// Gets the date of the current plan.
+    pub fn today(&self) -> NaiveDate {
+        self.plan_date(self.now())
+    }
+
+    /// Gets the date of the plan a timestamp belongs to.
+    pub fn plan_date(&self, timestamp: NaiveDateTime) -> NaiveDate {
+        let since_midnight = self.day_start - NaiveTime::from_hms(0, 0, 0);
+        (timestamp - since_midnight).date()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn fixed_clock() {
+        let monday = NaiveDate::from_ymd(2021, 5, 31);
+        let clock = Clock::fixed(monday);
+        assert_eq!(clock.today(), monday);
+
+        let clock = clock.with_day_start(NaiveTime::from_hms(23, 59, 59));
+        assert_eq!(clock.today(), monday);
+    }
+
+    #[test]
+    fn day_start() {
+        let clock = Clock::system().with_day_start(NaiveTime::from_hms(4, 0, 0));
+        let monday = NaiveDate::from_ymd(2021, 5, 31);
+
+        assert_eq!(clock.plan_date(monday.and_hms(4, 0, 0)), monday);
+        assert_eq!(clock.plan_date(monday.and_hms(23, 30, 0)), monday);
+        assert_eq!(clock.plan_date(monday.succ().and_hms(3, 59, 0)), monday);
+        assert_eq!(clock.plan_date(monday.and_hms(3, 59, 0)), monday.pred());
+    }
+}
@@ -0,0 +1,11 @@
+pub mod doctor;
+pub mod export;
+pub mod history;
+pub mod import_ics;
+pub mod init;
+pub mod now;
+pub mod publish;
+pub mod taskwarrior;
+pub mod today;
+pub mod todotxt;
+pub mod view;
@@ -0,0 +1,396 @@
+use std::{
+    collections::BTreeMap,
+    fmt, fs,
+    io::{self, BufRead, Write},
+    path::{Path, PathBuf},
+};
+
+use chrono::NaiveDate;
+
+use planner::{
+    io::{PlanDirectory, PlanFile},
+    plan::format,
+};
+
+/// A problem found in the plan directory.
+#[derive(Debug)]
+enum Problem {
+    /// The file couldn't be parsed as a plan.
+    Unparseable { path: PathBuf, error: String },
+    /// The file isn't where the file pattern puts the plan for its date, so
+    /// planner doesn't read it.
+    Misplaced { path: PathBuf, expected: PathBuf },
+    /// The header date doesn't match the date in the file name.
+    DateMismatch {
+        path: PathBuf,
+        file_date: NaiveDate,
+        header_date: NaiveDate,
+        text: String,
+    },
+    /// Several files have the same header date.
+    DuplicateDate {
+        date: NaiveDate,
+        paths: Vec<PathBuf>,
+    },
+    /// The line after the header isn't the weekday of the plan date.
+    WrongWeekday {
+        path: PathBuf,
+        date: NaiveDate,
+        found: String,
+        text: String,
+    },
+}
+
+impl fmt::Display for Problem {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Problem::Unparseable { path, error } => {
+                write!(f, "{}: could not parse plan: {}", path.display(), error)
+            }
+            Problem::Misplaced { path, expected } => write!(
+                f,
+                "{}: misplaced, planner reads the plan for this date from {}",
+                path.display(),
+                expected.display()
+            ),
+            Problem::DateMismatch {
+                path,
+                file_date,
+                header_date,
+                ..
+            } => write!(
+                f,
+                "{}: header date {} doesn't match file name date {}",
+                path.display(),
+                header_date.format("%m/%d/%Y"),
+                file_date.format("%m/%d/%Y")
+            ),
+            Problem::DuplicateDate { date, paths } => {
+                let paths: Vec<String> = paths.iter().map(|p| p.display().to_string()).collect();
+                write!(
+                    f,
+                    "{} plans are dated {}: {}",
+                    paths.len(),
+                    date.format("%m/%d/%Y"),
+                    paths.join(", ")
+                )
+            }
+            Problem::WrongWeekday {
+                path, date, found, ..
+            } => write!(
+                f,
+                "{}: weekday is `{}`, but {} is a {}",
+                path.display(),
+                found,
+                date.format("%m/%d/%Y"),
+                date.format("%A")
+            ),
+        }
+    }
+}
+
+/// A change that fixes a [Problem].
+enum Fix {
+    Rename {
+        from: NaiveDate,
+        to: NaiveDate,
+        from_path: PathBuf,
+        to_path: PathBuf,
+    },
+    Rewrite {
+        file: PlanFile,
+        text: String,
+    },
+}
+
+impl fmt::Display for Fix {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Fix::Rename {
+                from_path, to_path, ..
+            } => {
+                write!(f, "Rename {} to {}", from_path.display(), to_path.display())
+            }
+            Fix::Rewrite { file, .. } => {
+                write!(f, "Rewrite the header of {}", file.path().display())
+            }
+        }
+    }
+}
+
+/// Checks the plan directory for broken plans, and optionally fixes them.
+pub fn run(plan_dir: &PlanDirectory, fix: bool, yes: bool) -> anyhow::Result<()> {
+    let problems = diagnose(plan_dir)?;
+
+    if problems.is_empty() {
+        println!("No problems found.");
+        return Ok(());
+    }
+
+    for problem in &problems {
+        println!("{}", problem);
+    }
+    println!("{} problem(s) found.", problems.len());
+
+    if !fix {
+        return Ok(());
+    }
+
+    for problem in problems {
+        let mut fix = match get_fix(plan_dir, problem) {
+            Some(f) => f,
+            None => continue,
+        };
+
+        if !yes && !confirm(&format!("{}?", fix))? {
+            continue;
+        }
+
+        let result = match &mut fix {
+            Fix::Rename { from, to, .. } => {
+                plan_dir.move_plan(*from, *to).map_err(anyhow::Error::from)
+            }
+            Fix::Rewrite { file, text } => file.write(plan_dir, text).map_err(anyhow::Error::from),
+        };
+
+        match result {
+            Ok(()) => println!("{}: done.", fix),
+            Err(e) => println!("{}: failed: {:#}", fix, e),
+        }
+    }
+
+    Ok(())
+}
+
+/// Checks every file that looks like a plan, including ones in the wrong
+/// place, which loading plans by date would miss.
+fn diagnose(plan_dir: &PlanDirectory) -> anyhow::Result<Vec<Problem>> {
+    let mut problems = Vec::new();
+    let mut header_dates: BTreeMap<NaiveDate, Vec<PathBuf>> = BTreeMap::new();
+
+    for (file_date, path) in plan_dir.store().get_files()? {
+        let expected = plan_dir.get_plan_path(file_date);
+        if path != expected {
+            problems.push(Problem::Misplaced {
+                path: path.clone(),
+                expected,
+            });
+        }
+
+        let format = format::for_path(&path);
+        let text = match fs::read_to_string(&path) {
+            Ok(text) => text,
+            Err(e) => {
+                problems.push(Problem::Unparseable {
+                    path,
+                    error: e.to_string(),
+                });
+                continue;
+            }
+        };
+        let plan = match format.parse(&text) {
+            Ok(plan) => plan,
+            Err(e) => {
+                problems.push(Problem::Unparseable {
+                    path,
+                    error: e.to_string(),
+                });
+                continue;
+            }
+        };
+
+        let header_date = plan.date();
+        header_dates
+            .entry(header_date)
+            .or_default()
+            .push(path.clone());
+
+        if header_date != file_date {
+            problems.push(Problem::DateMismatch {
+                path,
+                file_date,
+                header_date,
+                text,
+            });
+        } else if let Some(found) = format.weekday_line(&text) {
+            if found.trim() != plan.day() {
+                let found = found.trim().to_string();
+                problems.push(Problem::WrongWeekday {
+                    path,
+                    date: header_date,
+                    found,
+                    text,
+                });
+            }
+        }
+    }
+
+    for (date, paths) in header_dates {
+        if paths.len() > 1 {
+            problems.push(Problem::DuplicateDate { date, paths });
+        }
+    }
+
+    Ok(problems)
+}
+
+/// Gets the fix for a problem.  Only plans in their expected place are fixed,
+/// since the fixes go through the plan directory.
+fn get_fix(plan_dir: &PlanDirectory, problem: Problem) -> Option<Fix> {
+    match problem {
+        Problem::DateMismatch {
+            path,
+            file_date,
+            header_date,
+            text,
+        } => {
+            let file = load_expected(plan_dir, file_date, &path)?;
+
+            // If the weekday agrees with the header, the file was most likely
+            // misnamed.  Otherwise, the header wasn't updated after a copy.
+            let header_weekday = header_date.format("%A").to_string();
+            let target = plan_dir.get_plan_path(header_date);
+            let weekday_matches_header =
+                file.format().weekday_line(&text).map(str::trim) == Some(header_weekday.as_str());
+
+            if weekday_matches_header && !target.exists() {
+                Some(Fix::Rename {
+                    from: file_date,
+                    to: header_date,
+                    from_path: path,
+                    to_path: target,
+                })
+            } else {
+                Some(Fix::Rewrite {
+                    text: file.format().rewrite_header(&text, file_date),
+                    file,
+                })
+            }
+        }
+        Problem::WrongWeekday {
+            path, date, text, ..
+        } => {
+            let file = load_expected(plan_dir, date, &path)?;
+            Some(Fix::Rewrite {
+                text: file.format().rewrite_header(&text, date),
+                file,
+            })
+        }
+        Problem::Unparseable { .. } | Problem::Misplaced { .. } | Problem::DuplicateDate { .. } => {
+            None
+        }
+    }
+}
+
+/// Loads the plan for a date, if the path is where it's expected.
+fn load_expected(plan_dir: &PlanDirectory, date: NaiveDate, path: &Path) -> Option<PlanFile> {
+    if path != plan_dir.get_plan_path(date) {
+        return None;
+    }
+    plan_dir.get_plan(date).ok()
+}
+
+fn confirm(prompt: &str) -> io::Result<bool> {
+    print!("{} [y/N] ", prompt);
+    io::stdout().flush()?;
+
+    let mut answer = String::new();
+    io::stdin().lock().read_line(&mut answer)?;
+    Ok(matches!(answer.trim(), "y" | "Y" | "yes"))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use planner::io::FilePattern;
+
+    #[test]
+    fn reports_misplaced_files() {
+        let dir = tempfile::tempdir().unwrap();
+        let plan_dir = PlanDirectory::new(dir.path().to_owned(), FilePattern::default());
+        fs::write(
+            dir.path().join("2021.6.1.plan.md"),
+            "# 06/01/2021\nTuesday\n",
+        )
+        .unwrap();
+
+        let problems = diagnose(&plan_dir).unwrap();
+        assert!(matches!(
+            problems.as_slice(),
+            [Problem::Misplaced { expected, .. }]
+                if *expected == dir.path().join("2021.06.01.plan.md")
+        ));
+    }
+
+    #[test]
+    fn reports_date_problems() {
+        let dir = tempfile::tempdir().unwrap();
+        let plan_dir = PlanDirectory::new(dir.path().to_owned(), FilePattern::default());
+        let path = |name: &str| dir.path().join(name);
+        fs::write(path("2021.06.01.plan.md"), "# 06/01/2021\nMonday\n").unwrap();
+        fs::write(path("2021.06.02.plan.md"), "# 06/01/2021\nTuesday\n").unwrap();
+        fs::write(path("2021.06.03.plan.md"), b"# 06/03/2021\n\xff\n").unwrap();
+
+        let problems = diagnose(&plan_dir).unwrap();
+        let june = |day| NaiveDate::from_ymd(2021, 6, day);
+        assert!(matches!(
+            problems.as_slice(),
+            [
+                Problem::WrongWeekday { date, found, .. },
+                Problem::DateMismatch { file_date, header_date, .. },
+                Problem::Unparseable { path: unreadable, .. },
+                Problem::DuplicateDate { date: duplicate, paths },
+            ] if *date == june(1)
+                && found == "Monday"
+                && *file_date == june(2)
+                && *header_date == june(1)
+                && *unreadable == path("2021.06.03.plan.md")
+                && *duplicate == june(1)
+                && *paths == vec![path("2021.06.01.plan.md"), path("2021.06.02.plan.md")]
+        ));
+
+        let fix = problems.into_iter().find_map(|p| match p {
+            Problem::WrongWeekday { .. } => get_fix(&plan_dir, p),
+            _ => None,
+        });
+        assert!(
+            matches!(fix, Some(Fix::Rewrite { text, .. }) if text == "# 06/01/2021\nTuesday\n")
+        );
+    }
+
+    #[test]
+    fn renames_or_rewrites_mismatched_plans() {
+        let dir = tempfile::tempdir().unwrap();
+        let plan_dir = PlanDirectory::new(dir.path().to_owned(), FilePattern::default());
+        let path = |name: &str| dir.path().join(name);
+        let fixes = || -> Vec<Fix> {
+            let problems = diagnose(&plan_dir).unwrap();
+            problems
+                .into_iter()
+                .filter_map(|p| get_fix(&plan_dir, p))
+                .collect()
+        };
+
+        // The weekday agrees with the header, so the file was misnamed.
+        fs::write(path("2021.06.01.plan.md"), "# 06/02/2021\nWednesday\n").unwrap();
+        assert!(matches!(
+            fixes().as_slice(),
+            [Fix::Rename { to, .. }] if *to == NaiveDate::from_ymd(2021, 6, 2)
+        ));
+
+        // The weekday agrees with the file name, so the header wasn't updated.
+        fs::write(path("2021.06.01.plan.md"), "# 06/02/2021\nTuesday\n").unwrap();
+        assert!(matches!(
+            fixes().as_slice(),
+            [Fix::Rewrite { text, .. }] if text == "# 06/01/2021\nTuesday\n"
+        ));
+
+        // A misnamed file isn't renamed over another plan.
+        fs::write(path("2021.06.01.plan.md"), "# 06/02/2021\nWednesday\n").unwrap();
+        fs::write(path("2021.06.02.plan.md"), "# 06/02/2021\nWednesday\n").unwrap();
+        assert!(matches!(
+            fixes().as_slice(),
+            [Fix::Rewrite { text, .. }] if text == "# 06/01/2021\nTuesday\n"
+        ));
+    }
+}
@@ -0,0 +1,223 @@
+mod ics;
+
+use std::{fs, ops::Bound, path::Path};
+
+use anyhow::Context;
+use chrono::{NaiveDate, NaiveTime};
+use planner::{Plan, PlanDirectory, ScheduleSection, TaskStatus};
+use serde::Serialize;
+
+use crate::cli::{ExportFormat, ExportOptions};
+
+const TASKS_FILE: &str = "tasks.csv";
+const EVENTS_FILE: &str = "events.csv";
+const CALENDAR_FILE: &str = "planner.ics";
+
+#[derive(Debug, Serialize)]
+struct TaskRow<'a> {
+    date: NaiveDate,
+    category: &'a str,
+    description: &'a str,
+    status: &'a TaskStatus,
+}
+
+#[derive(Debug, Serialize)]
+struct EventRow<'a> {
+    date: NaiveDate,
+    section: ScheduleSection,
+    start: String,
+    end: Option<String>,
+    description: &'a str,
+}
+
+/// Exports the plans in a date range.  Calendar events are placed on each
+/// plan's day, which starts at `day_start`.
+pub fn run(
+    plan_dir: &PlanDirectory,
+    options: &ExportOptions,
+    day_start: NaiveTime,
+) -> anyhow::Result<()> {
+    let plans: Vec<Plan> = load_plans(plan_dir, options.from, options.to)?
+        .into_iter()
+        .map(|(_, plan)| plan)
+        .collect();
+
+    fs::create_dir_all(&options.out)
+        .with_context(|| format!("Could not create export directory {:?}", options.out))?;
+
+    match options.format {
+        ExportFormat::Csv => export_csv(&plans, &options.out),
+        ExportFormat::Ics => export_ics(&plans, &options.out, options.actual, day_start),
+    }
+}
+
+/// Loads the plans in a date range, oldest first, with the date of their file.
+/// Plans that can't be parsed are reported and skipped.
+pub fn load_plans(
+    plan_dir: &PlanDirectory,
+    from: Option<NaiveDate>,
+    to: Option<NaiveDate>,
+) -> anyhow::Result<Vec<(NaiveDate, Plan)>> {
+    let from = from.map_or(Bound::Unbounded, Bound::Included);
+    let to = to.map_or(Bound::Unbounded, Bound::Included);
+
+    let mut plans = Vec::new();
+    for (date, loaded) in plan_dir.load_range((from, to))? {
+        match loaded {
+            Ok(file) => plans.push((date, file.plan().clone())),
+            Err(e) => eprintln!("Skipped {:#}", anyhow::Error::from(e)),
+        }
+    }
+
+    Ok(plans)
+}
+
+fn export_csv(plans: &[Plan], out: &Path) -> anyhow::Result<()> {
+    let tasks_path = out.join(TASKS_FILE);
+    let tasks = write_csv(&tasks_path, task_rows(plans))?;
+
+    let events_path = out.join(EVENTS_FILE);
+    let events = write_csv(&events_path, event_rows(plans))?;
+
+    println!(
+        "Exported {} task(s) to {} and {} event(s) to {}.",
+        tasks,
+        tasks_path.display(),
+        events,
+        events_path.display()
+    );
+    Ok(())
+}
+
+fn export_ics(
+    plans: &[Plan],
+    out: &Path,
+    actual: bool,
+    day_start: NaiveTime,
+) -> anyhow::Result<()> {
+    let path = out.join(CALENDAR_FILE);
+    let stamp = chrono::Utc::now().naive_utc();
+
+    fs::write(&path, ics::render(plans, actual, day_start, stamp))
+        .with_context(|| format!("Could not write {:?}", path))?;
+
+    println!("Exported {} plan(s) to {}.", plans.len(), path.display());
+    Ok(())
+}
+
+/// Writes rows to a CSV file with a header, returning the number of rows.
+fn write_csv<T: Serialize>(path: &Path, rows: Vec<T>) -> anyhow::Result<usize> {
+    let mut writer =
+        csv::Writer::from_path(path).with_context(|| format!("Could not create {:?}", path))?;
+
+    for row in &rows {
+        writer.serialize(row)?;
+    }
+    writer.flush()?;
+
+    Ok(rows.len())
+}
+
+fn task_rows(plans: &[Plan]) -> Vec<TaskRow<'_>> {
+    let mut rows = Vec::new();
+
+    for plan in plans {
+        let categories = plan.tasks().iter().flat_map(|t| t.categories());
+        for category in categories {
+            for task in category.tasks() {
+                rows.push(TaskRow {
+                    date: plan.date(),
+                    category: category.name(),
+                    description: task.description(),
+                    status: task.status(),
+                });
+            }
+        }
+    }
+
+    rows
+}
+
+fn event_rows(plans: &[Plan]) -> Vec<EventRow<'_>> {
+    let mut rows = Vec::new();
+
+    for plan in plans {
+        let schedule = match plan.schedule() {
+            Some(s) => s,
+            None => continue,
+        };
+
+        for &section in &[ScheduleSection::Planned, ScheduleSection::Actual] {
+            for (event, end) in schedule.events_with_end(section) {
+                rows.push(EventRow {
+                    date: plan.date(),
+                    section,
+                    start: format_time(event.start()),
+                    end: end.as_ref().map(format_time),
+                    description: event.description(),
+                });
+            }
+        }
+    }
+
+    rows
+}
+
+fn format_time(time: &NaiveTime) -> String {
+    time.format("%H:%M").to_string()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use indoc::indoc;
+
+    #[test]
+    fn flattens_plans() {
+        let plan = Plan::from_markdown(indoc! {"
+            # 06/01/2021
+            Tuesday
+
+            ## Tasks
+            - **Work**
+              - [x] Write report
+              - [ ] Review pull requests
+
+            ## Schedule
+            - **Planned**
+              - 0930\tStandup
+              - 1000\tFocus time
+            - **Actual**
+              - 0945\tStandup
+        "})
+        .unwrap();
+        let plans = vec![plan];
+
+        let mut writer = csv::Writer::from_writer(Vec::new());
+        for row in task_rows(&plans) {
+            writer.serialize(row).unwrap();
+        }
+        assert_eq!(
+            String::from_utf8(writer.into_inner().unwrap()).unwrap(),
+            indoc! {"
+                date,category,description,status
+                2021-06-01,Work,Write report,complete
+                2021-06-01,Work,Review pull requests,incomplete
+            "}
+        );
+
+        let mut writer = csv::Writer::from_writer(Vec::new());
+        for row in event_rows(&plans) {
+            writer.serialize(row).unwrap();
+        }
+        assert_eq!(
+            String::from_utf8(writer.into_inner().unwrap()).unwrap(),
+            indoc! {"
+                date,section,start,end,description
+                2021-06-01,planned,09:30,10:00,Standup
+                2021-06-01,planned,10:00,,Focus time
+                2021-06-01,actual,09:45,,Standup
+            "}
+        );
+    }
+}
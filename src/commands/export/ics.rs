@@ -0,0 +1,233 @@
+use std::fmt::Write;
+
+use chrono::{NaiveDate, NaiveDateTime, NaiveTime};
+use planner::{Event, Plan, ScheduleSection};
+
+/// The longest line allowed by RFC 5545, in bytes.
+const MAX_LINE_LENGTH: usize = 75;
+
+/// Renders the events of several plans as an iCalendar document.
+///
+/// Every event gets a UID made from its date, section and description, so that
+/// calendar apps update previously imported events instead of duplicating
+/// them, even after the event is moved to another time.  Events are placed on
+/// each plan's day, which starts at `day_start`.
+pub fn render(plans: &[Plan], actual: bool, day_start: NaiveTime, stamp: NaiveDateTime) -> String {
+    let mut lines = vec![
+        "BEGIN:VCALENDAR".to_string(),
+        "VERSION:2.0".to_string(),
+        format!(
+            "PRODID:-//planner//planner {}//EN",
+            env!("CARGO_PKG_VERSION")
+        ),
+    ];
+
+    let mut sections = vec![ScheduleSection::Planned];
+    if actual {
+        sections.push(ScheduleSection::Actual);
+    }
+
+    for plan in plans {
+        let schedule = match plan.schedule() {
+            Some(s) => s,
+            None => continue,
+        };
+
+        for &section in &sections {
+            let spans = schedule.spans(section, plan.date(), day_start);
+
+            for (i, span) in spans.iter().enumerate() {
+                let event = span.event;
+
+                // Events with the same description are numbered in order.
+                let mut uid = get_uid(plan.date(), event, section);
+                let earlier = spans[..i]
+                    .iter()
+                    .filter(|s| slug(s.event.description()) == slug(event.description()))
+                    .count();
+                if earlier > 0 {
+                    uid = format!("{}-{}", uid, earlier + 1);
+                }
+
+                lines.push("BEGIN:VEVENT".to_string());
+                lines.push(format!("UID:{}@planner", uid));
+                lines.push(format!("DTSTAMP:{}Z", format_date_time(stamp)));
+                lines.push(format!("DTSTART:{}", format_date_time(span.start)));
+                lines.push(format!("DTEND:{}", format_date_time(span.end)));
+                lines.push(format!("SUMMARY:{}", escape(event.description())));
+                if section == ScheduleSection::Actual {
+                    lines.push("CATEGORIES:Actual".to_string());
+                }
+                lines.push("END:VEVENT".to_string());
+            }
+        }
+    }
+
+    lines.push("END:VCALENDAR".to_string());
+
+    let mut ics = String::new();
+    for line in lines {
+        write!(ics, "{}\r\n", fold(&line)).unwrap();
+    }
+    ics
+}
+
+fn get_uid(date: NaiveDate, event: &Event, section: ScheduleSection) -> String {
+    let section = match section {
+        ScheduleSection::Planned => "planned",
+        ScheduleSection::Actual => "actual",
+    };
+
+    format!(
+        "{}-{}-{}",
+        date.format("%Y%m%d"),
+        section,
+        slug(event.description())
+    )
+}
+
+/// Turns a description into lowercase words joined by hyphens, like
+/// `standup-then-coffee`.
+fn slug(description: &str) -> String {
+    let words: Vec<String> = description
+        .split(|c: char| !c.is_alphanumeric())
+        .filter(|w| !w.is_empty())
+        .map(str::to_lowercase)
+        .collect();
+
+    if words.is_empty() {
+        "event".to_string()
+    } else {
+        words.join("-")
+    }
+}
+
+fn format_date_time(date_time: NaiveDateTime) -> String {
+    date_time.format("%Y%m%dT%H%M%S").to_string()
+}
+
+/// Escapes text for use in a property value.
+fn escape(text: &str) -> String {
+    text.replace('\\', "\\\\")
+        .replace(';', "\\;")
+        .replace(',', "\\,")
+        .replace('\n', "\\n")
+}
+
+/// Splits a long content line into continuation lines, without splitting
+/// characters.
+fn fold(line: &str) -> String {
+    let mut folded = String::new();
+    let mut length = 0;
+
+    for c in line.chars() {
+        if length + c.len_utf8() > MAX_LINE_LENGTH {
+            folded.push_str("\r\n ");
+            length = 1;
+        }
+        folded.push(c);
+        length += c.len_utf8();
+    }
+
+    folded
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use indoc::indoc;
+
+    #[test]
+    fn renders_events() {
+        let plan = Plan::from_markdown(indoc! {"
+            # 06/01/2021
+            Tuesday
+
+            ## Tasks
+            - **Work**
+              - [ ] Write report
+
+            ## Schedule
+            - **Planned**
+              - 0930\tStandup, then coffee
+              - 1000\tFocus time
+            - **Actual**
+              - 0945\tStandup
+        "})
+        .unwrap();
+        let plans = vec![plan];
+        let stamp = NaiveDate::from_ymd(2021, 6, 2).and_hms(12, 0, 0);
+
+        let midnight = NaiveTime::from_hms(0, 0, 0);
+
+        let ics = render(&plans, false, midnight, stamp);
+        assert_eq!(
+            ics.replace("\r\n", "\n"),
+            indoc! {"
+                BEGIN:VCALENDAR
+                VERSION:2.0
+                PRODID:-//planner//planner 0.3.0//EN
+                BEGIN:VEVENT
+                UID:20210601-planned-standup-then-coffee@planner
+                DTSTAMP:20210602T120000Z
+                DTSTART:20210601T093000
+                DTEND:20210601T100000
+                SUMMARY:Standup\\, then coffee
+                END:VEVENT
+                BEGIN:VEVENT
+                UID:20210601-planned-focus-time@planner
+                DTSTAMP:20210602T120000Z
+                DTSTART:20210601T100000
+                DTEND:20210601T103000
+                SUMMARY:Focus time
+                END:VEVENT
+                END:VCALENDAR
+            "}
+            .replace("0.3.0", env!("CARGO_PKG_VERSION"))
+        );
+
+        let ics = render(&plans, true, midnight, stamp);
+        assert!(ics.contains("UID:20210601-actual-standup@planner\r\n"));
+    }
+
+    #[test]
+    fn renders_events_after_midnight() {
+        let plan = Plan::from_markdown(indoc! {"
+            # 06/01/2021
+            Tuesday
+
+            ## Tasks
+            - **Work**
+              - [ ] Write report
+
+            ## Schedule
+            - **Planned**
+              - 2300\tRelease
+              - 0100\tSleep
+        "})
+        .unwrap();
+        let plans = vec![plan];
+        let stamp = NaiveDate::from_ymd(2021, 6, 2).and_hms(12, 0, 0);
+
+        let ics = render(&plans, false, NaiveTime::from_hms(4, 0, 0), stamp);
+        assert!(ics.contains(concat!(
+            "DTSTART:20210601T230000\r\n",
+            "DTEND:20210602T010000\r\n",
+            "SUMMARY:Release\r\n"
+        )));
+        assert!(ics.contains(concat!(
+            "DTSTART:20210602T010000\r\n",
+            "DTEND:20210602T013000\r\n",
+            "SUMMARY:Sleep\r\n"
+        )));
+    }
+
+    #[test]
+    fn folds_long_lines() {
+        let line = format!("SUMMARY:{}", "é".repeat(50));
+        let folded = fold(&line);
+
+        assert!(folded.split("\r\n").all(|l| l.len() <= MAX_LINE_LENGTH));
+        assert_eq!(folded.replace("\r\n ", ""), line);
+    }
+}
@@ -0,0 +1,112 @@
+use chrono::NaiveDate;
+
+use planner::io::{PlanDirectory, PlanStore};
+
+/// Lists the saved versions of the plan for a date.
+pub fn list(plan_dir: &PlanDirectory, date: NaiveDate) -> anyhow::Result<()> {
+    let plan_path = plan_dir.get_plan_path(date);
+    let store = plan_dir.store();
+    let versions = store.history().versions(store.relative_path(&plan_path))?;
+
+    if versions.is_empty() {
+        println!("No saved versions of {}.", plan_path.display());
+        return Ok(());
+    }
+
+    println!("Saved versions of {}:", plan_path.display());
+    for (i, version) in versions.iter().rev().enumerate() {
+        let lines = version.read()?.lines().count();
+        println!(
+            "{:>3}  {}  ({} lines)",
+            i + 1,
+            version.timestamp().format("%Y-%m-%d %H:%M:%S"),
+            lines
+        );
+    }
+
+    Ok(())
+}
+
+/// Restores the most recent saved version of the plan for a date.  The
+/// replaced contents are kept for [redo], apart from the saved versions, so
+/// that repeated undos step further back.
+pub fn undo(plan_dir: &PlanDirectory, date: NaiveDate) -> anyhow::Result<()> {
+    let mut file = plan_dir.get_plan(date)?;
+    let store = plan_dir.store();
+    let history = store.history();
+    let relative_path = store.relative_path(file.path()).to_owned();
+
+    let version = match history.versions(&relative_path)?.pop() {
+        Some(version) => version,
+        None => {
+            println!("No saved versions of {}.", file.path().display());
+            return Ok(());
+        }
+    };
+
+    let replaced = store.read(date)?;
+    file.write(plan_dir, &version.read()?)?;
+
+    // Writing saved the replaced contents as the newest version; move them to
+    // the redo entry instead, and drop the restored version.
+    history.pop(&relative_path)?;
+    history.remove(&version)?;
+    history.save_redo(&relative_path, &replaced)?;
+
+    println!(
+        "Restored {} to the version from {}.",
+        file.path().display(),
+        version.timestamp().format("%Y-%m-%d %H:%M:%S")
+    );
+    Ok(())
+}
+
+/// Restores the contents of the plan for a date that the last [undo]
+/// replaced, if the plan hasn't been changed since.
+pub fn redo(plan_dir: &PlanDirectory, date: NaiveDate) -> anyhow::Result<()> {
+    let mut file = plan_dir.get_plan(date)?;
+    let store = plan_dir.store();
+
+    match store.history().redo(store.relative_path(file.path()))? {
+        Some(contents) => {
+            file.write(plan_dir, &contents)?;
+            println!("Redid the last undo of {}.", file.path().display());
+        }
+        None => println!("Nothing to redo for {}.", file.path().display()),
+    }
+
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use planner::io::FilePattern;
+
+    #[test]
+    fn undoes_step_by_step() {
+        let dir = tempfile::tempdir().unwrap();
+        let plan_dir = PlanDirectory::new(dir.path().to_owned(), FilePattern::default());
+        let date = NaiveDate::from_ymd(2021, 6, 1);
+        let version = |n| format!("# 06/01/2021\nTuesday\n\n## Notes\n{}\n", n);
+        let read = || plan_dir.store().read(date).unwrap();
+
+        plan_dir.store().create(date, &version(1)).unwrap();
+        for n in 2..=3 {
+            plan_dir.store().write(date, &version(n)).unwrap();
+        }
+
+        undo(&plan_dir, date).unwrap();
+        assert_eq!(read(), version(2));
+        undo(&plan_dir, date).unwrap();
+        assert_eq!(read(), version(1));
+
+        redo(&plan_dir, date).unwrap();
+        assert_eq!(read(), version(2));
+        redo(&plan_dir, date).unwrap();
+        assert_eq!(read(), version(2));
+
+        undo(&plan_dir, date).unwrap();
+        assert_eq!(read(), version(1));
+    }
+}
@@ -0,0 +1,70 @@
+mod calendar;
+
+use std::{fs, path::Path};
+
+use anyhow::Context;
+use chrono::{NaiveDate, NaiveTime};
+use planner::{io::PlanStore, Event, PlanDirectory, PlanFile};
+
+/// Imports the events on a date from a calendar file into the plan for that
+/// date.
+pub fn run(
+    plan_dir: &PlanDirectory,
+    calendar: &Path,
+    date: NaiveDate,
+    day_start: NaiveTime,
+) -> anyhow::Result<()> {
+    let mut file = plan_dir.get_or_create_plan(date)?;
+    let imported = import(plan_dir, &mut file, calendar, day_start)?;
+
+    println!(
+        "Imported {} event(s) into {}.",
+        imported,
+        file.path().display()
+    );
+    Ok(())
+}
+
+/// Adds the events from a calendar file to a plan's planned schedule, skipping
+/// events the plan already has, and keeping them in order on the plan's day,
+/// which starts at `day_start`.  Returns the number of events added.
+pub fn import(
+    plan_dir: &PlanDirectory,
+    file: &mut PlanFile,
+    calendar: &Path,
+    day_start: NaiveTime,
+) -> anyhow::Result<usize> {
+    let text = fs::read_to_string(calendar)
+        .with_context(|| format!("Could not read calendar {:?}", calendar))?;
+    let events =
+        calendar::parse(&text).with_context(|| format!("Invalid calendar {:?}", calendar))?;
+
+    let planned = file
+        .plan()
+        .schedule()
+        .as_ref()
+        .map_or(&[][..], |s| s.planned());
+    let new: Vec<Event> = calendar::events_on(&events, file.date())
+        .into_iter()
+        .filter(|e| !planned.iter().any(|p| is_same_event(p, e)))
+        .collect();
+
+    if new.is_empty() {
+        return Ok(0);
+    }
+
+    let contents = plan_dir.store().read(file.date())?;
+    file.write(
+        plan_dir,
+        &file.format().insert_planned(&contents, &new, day_start)?,
+    )?;
+
+    Ok(new.len())
+}
+
+fn is_same_event(a: &Event, b: &Event) -> bool {
+    a.start() == b.start()
+        && a.description()
+            .trim()
+            .eq_ignore_ascii_case(b.description().trim())
+}
@@ -0,0 +1,522 @@
+//! A minimal iCalendar reader: just enough of RFC 5545 to find the timed
+//! events on a day, including recurring ones.
+
+use std::collections::HashSet;
+
+use anyhow::Context;
+use chrono::{
+    Datelike, Duration, Local, NaiveDate, NaiveDateTime, NaiveTime, TimeZone, Utc, Weekday,
+};
+use planner::Event;
+
+/// A `VEVENT` from a calendar.
+#[derive(Debug, Clone, PartialEq)]
+pub struct CalendarEvent {
+    uid: Option<String>,
+    summary: String,
+    start: NaiveDateTime,
+    recurrence: Option<Recurrence>,
+    exceptions: Vec<NaiveDate>,
+    recurrence_id: Option<NaiveDate>,
+    cancelled: bool,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+enum Frequency {
+    Daily,
+    Weekly,
+    Monthly,
+    Yearly,
+}
+
+/// A recurrence rule (`RRULE`).
+#[derive(Debug, Clone, PartialEq)]
+struct Recurrence {
+    frequency: Frequency,
+    interval: u32,
+    count: Option<u32>,
+    until: Option<NaiveDate>,
+    /// Weekdays, with an optional ordinal such as the `-1` in `-1FR`.
+    by_day: Vec<(Option<i32>, Weekday)>,
+    by_month_day: Vec<i32>,
+    by_month: Vec<u32>,
+}
+
+/// Parses the events of a calendar.  All-day events are skipped, since they
+/// don't belong in a schedule.
+pub fn parse(text: &str) -> anyhow::Result<Vec<CalendarEvent>> {
+    let mut events = Vec::new();
+    let mut current: Option<Vec<(String, String)>> = None;
+
+    for line in unfold(text) {
+        let (name, value) = match split_property(&line) {
+            Some(p) => p,
+            None => continue,
+        };
+
+        match (name.as_str(), value.as_str()) {
+            ("BEGIN", "VEVENT") => current = Some(Vec::new()),
+            ("END", "VEVENT") => {
+                if let Some(properties) = current.take() {
+                    if let Some(event) = parse_event(&properties)? {
+                        events.push(event);
+                    }
+                }
+            }
+            _ => {
+                if let Some(properties) = &mut current {
+                    properties.push((name, value));
+                }
+            }
+        }
+    }
+
+    Ok(events)
+}
+
+/// Gets the events that take place on a date, sorted by start time.
+pub fn events_on(events: &[CalendarEvent], date: NaiveDate) -> Vec<Event> {
+    // Instances that were moved or cancelled replace the regular occurrence.
+    let overridden: HashSet<(&str, NaiveDate)> = events
+        .iter()
+        .filter_map(|e| Some((e.uid.as_deref()?, e.recurrence_id?)))
+        .collect();
+
+    let mut found: Vec<Event> = events
+        .iter()
+        .filter(|e| !e.cancelled && e.occurs_on(date))
+        .filter(|e| {
+            e.recurrence_id.is_some()
+                || match &e.uid {
+                    Some(uid) => !overridden.contains(&(uid.as_str(), date)),
+                    None => true,
+                }
+        })
+        .map(|e| Event::new(e.start.time(), &e.summary))
+        .collect();
+
+    found.sort_by_key(|e| *e.start());
+    found
+}
+
+impl CalendarEvent {
+    fn occurs_on(&self, date: NaiveDate) -> bool {
+        let start = self.start.date();
+
+        match &self.recurrence {
+            _ if self.exceptions.contains(&date) => false,
+            Some(rule) if self.recurrence_id.is_none() => rule.occurs_on(start, date),
+            _ => start == date,
+        }
+    }
+}
+
+impl Recurrence {
+    /// Parses a recurrence rule.  Rules that repeat more often than daily
+    /// give `None`, since a plan only has one schedule per day.
+    fn parse(value: &str) -> anyhow::Result<Option<Self>> {
+        let mut frequency = None;
+        let mut rule = Recurrence {
+            frequency: Frequency::Daily,
+            interval: 1,
+            count: None,
+            until: None,
+            by_day: Vec::new(),
+            by_month_day: Vec::new(),
+            by_month: Vec::new(),
+        };
+
+        for part in value.split(';') {
+            let (key, value) = match part.split_once('=') {
+                Some(kv) => kv,
+                None => continue,
+            };
+
+            match key {
+                "FREQ" => {
+                    frequency = Some(match value {
+                        "DAILY" => Frequency::Daily,
+                        "WEEKLY" => Frequency::Weekly,
+                        "MONTHLY" => Frequency::Monthly,
+                        "YEARLY" => Frequency::Yearly,
+                        "HOURLY" | "MINUTELY" | "SECONDLY" => return Ok(None),
+                        _ => anyhow::bail!("unsupported frequency {}", value),
+                    })
+                }
+                "INTERVAL" => rule.interval = value.parse()?,
+                "COUNT" => rule.count = Some(value.parse()?),
+                "UNTIL" => rule.until = Some(parse_date_time(value)?.date()),
+                "BYDAY" => {
+                    for day in value.split(',') {
+                        if !day.is_ascii() {
+                            anyhow::bail!("invalid weekday {}", day);
+                        }
+                        let split = day.len().saturating_sub(2);
+                        let ordinal = match &day[..split] {
+                            "" | "+" => None,
+                            n => Some(n.trim_start_matches('+').parse()?),
+                        };
+                        rule.by_day.push((ordinal, parse_weekday(&day[split..])?));
+                    }
+                }
+                "BYMONTHDAY" => {
+                    for day in value.split(',') {
+                        rule.by_month_day.push(day.parse()?);
+                    }
+                }
+                "BYMONTH" => {
+                    for month in value.split(',') {
+                        rule.by_month.push(month.parse()?);
+                    }
+                }
+                _ => log::debug!("Ignoring recurrence rule part {}", part),
+            }
+        }
+
+        rule.frequency = frequency.context("recurrence rule has no FREQ")?;
+        rule.interval = rule.interval.max(1);
+        Ok(Some(rule))
+    }
+
+    /// Checks whether the rule, starting on `start`, has an occurrence on
+    /// `date`.
+    fn occurs_on(&self, start: NaiveDate, date: NaiveDate) -> bool {
+        if !self.matches(start, date) || self.until.is_some_and(|u| date > u) {
+            return false;
+        }
+
+        match self.count {
+            Some(count) => {
+                let mut day = start;
+                let mut occurrences = 0;
+                while day < date {
+                    if self.matches(start, day) {
+                        occurrences += 1;
+                    }
+                    day = day.succ();
+                }
+                occurrences < count
+            }
+            None => true,
+        }
+    }
+
+    /// Checks whether `date` fits the rule, ignoring `COUNT` and `UNTIL`.
+    fn matches(&self, start: NaiveDate, date: NaiveDate) -> bool {
+        if date < start {
+            return false;
+        }
+        if !self.by_month.is_empty() && !self.by_month.contains(&date.month()) {
+            return false;
+        }
+
+        let interval = self.interval as i64;
+        match self.frequency {
+            Frequency::Daily => {
+                (date - start).num_days() % interval == 0
+                    && (self.by_day.is_empty() || self.matches_weekday(date))
+                    && (self.by_month_day.is_empty() || self.matches_month_day(date))
+            }
+            Frequency::Weekly => {
+                let weeks = (week_start(date) - week_start(start)).num_weeks();
+                let weekday = if self.by_day.is_empty() {
+                    date.weekday() == start.weekday()
+                } else {
+                    self.matches_weekday(date)
+                };
+                weeks % interval == 0 && weekday
+            }
+            Frequency::Monthly => {
+                let months = months_between(start, date);
+                months % interval == 0 && self.matches_day_of_month(start, date)
+            }
+            Frequency::Yearly => {
+                let years = (date.year() - start.year()) as i64;
+                let month = !self.by_month.is_empty() || date.month() == start.month();
+                years % interval == 0 && month && self.matches_day_of_month(start, date)
+            }
+        }
+    }
+
+    fn matches_day_of_month(&self, start: NaiveDate, date: NaiveDate) -> bool {
+        if !self.by_day.is_empty() {
+            self.matches_weekday(date)
+        } else if !self.by_month_day.is_empty() {
+            self.matches_month_day(date)
+        } else {
+            date.day() == start.day()
+        }
+    }
+
+    fn matches_weekday(&self, date: NaiveDate) -> bool {
+        let from_start = (date.day() as i32 - 1) / 7 + 1;
+        let from_end = -((days_in_month(date) as i32 - date.day() as i32) / 7 + 1);
+
+        self.by_day.iter().any(|&(ordinal, weekday)| {
+            weekday == date.weekday()
+                && match ordinal {
+                    None => true,
+                    Some(n) => n == from_start || n == from_end,
+                }
+        })
+    }
+
+    fn matches_month_day(&self, date: NaiveDate) -> bool {
+        let from_end = date.day() as i32 - days_in_month(date) as i32 - 1;
+
+        self.by_month_day
+            .iter()
+            .any(|&day| day == date.day() as i32 || day == from_end)
+    }
+}
+
+fn parse_event(properties: &[(String, String)]) -> anyhow::Result<Option<CalendarEvent>> {
+    let get = |name: &str| properties.iter().find(|(n, _)| n == name).map(|(_, v)| v);
+
+    let start = match get("DTSTART") {
+        Some(value) if value.contains('T') => parse_date_time(value)?,
+        _ => return Ok(None),
+    };
+
+    let recurrence = match get("RRULE") {
+        Some(value) => {
+            match Recurrence::parse(value).with_context(|| format!("Invalid RRULE {:?}", value))? {
+                Some(rule) => Some(rule),
+                None => {
+                    log::warn!(
+                        "Skipping event {:?}, which repeats more often than daily",
+                        get("SUMMARY").map_or("", |v| v.as_str())
+                    );
+                    return Ok(None);
+                }
+            }
+        }
+        None => None,
+    };
+
+    let mut exceptions = Vec::new();
+    for (_, value) in properties.iter().filter(|(n, _)| n == "EXDATE") {
+        for value in value.split(',') {
+            exceptions.push(parse_date_time(value)?.date());
+        }
+    }
+
+    let recurrence_id = match get("RECURRENCE-ID") {
+        Some(value) => Some(parse_date_time(value)?.date()),
+        None => None,
+    };
+
+    Ok(Some(CalendarEvent {
+        uid: get("UID").cloned(),
+        summary: get("SUMMARY").map_or_else(String::new, |v| unescape(v)),
+        start,
+        recurrence,
+        exceptions,
+        recurrence_id,
+        cancelled: get("STATUS").is_some_and(|v| v == "CANCELLED"),
+    }))
+}
+
+/// Parses a date or date-time value as local time.  UTC times are converted
+/// to the local time zone; times with a `TZID` are taken to be local already.
+fn parse_date_time(value: &str) -> anyhow::Result<NaiveDateTime> {
+    let invalid = || format!("Invalid date-time {:?}", value);
+
+    if let Some(utc) = value.strip_suffix('Z') {
+        let utc = NaiveDateTime::parse_from_str(utc, "%Y%m%dT%H%M%S").with_context(invalid)?;
+        return Ok(Utc
+            .from_utc_datetime(&utc)
+            .with_timezone(&Local)
+            .naive_local());
+    }
+
+    if value.contains('T') {
+        return NaiveDateTime::parse_from_str(value, "%Y%m%dT%H%M%S").with_context(invalid);
+    }
+
+    let date = NaiveDate::parse_from_str(value, "%Y%m%d").with_context(invalid)?;
+    Ok(date.and_time(NaiveTime::from_hms(0, 0, 0)))
+}
+
+fn parse_weekday(day: &str) -> anyhow::Result<Weekday> {
+    Ok(match day {
+        "MO" => Weekday::Mon,
+        "TU" => Weekday::Tue,
+        "WE" => Weekday::Wed,
+        "TH" => Weekday::Thu,
+        "FR" => Weekday::Fri,
+        "SA" => Weekday::Sat,
+        "SU" => Weekday::Sun,
+        _ => anyhow::bail!("invalid weekday {}", day),
+    })
+}
+
+/// Joins folded content lines.
+fn unfold(text: &str) -> Vec<String> {
+    let mut lines: Vec<String> = Vec::new();
+
+    for line in text.lines() {
+        match (
+            line.strip_prefix(' ').or_else(|| line.strip_prefix('\t')),
+            lines.last_mut(),
+        ) {
+            (Some(rest), Some(last)) => last.push_str(rest),
+            _ => lines.push(line.to_string()),
+        }
+    }
+
+    lines
+}
+
+/// Splits a content line into its name and value, dropping any parameters.
+fn split_property(line: &str) -> Option<(String, String)> {
+    let mut quoted = false;
+    let colon = line.char_indices().find_map(|(i, c)| match c {
+        '"' => {
+            quoted = !quoted;
+            None
+        }
+        ':' if !quoted => Some(i),
+        _ => None,
+    })?;
+
+    let name = line[..colon].split(';').next().unwrap_or_default();
+    Some((name.to_uppercase(), line[colon + 1..].to_string()))
+}
+
+/// Unescapes a text value.  Line breaks become spaces, since events are
+/// written on a single line.
+fn unescape(text: &str) -> String {
+    let mut unescaped = String::new();
+    let mut chars = text.chars();
+
+    while let Some(c) = chars.next() {
+        match (c, c == '\\') {
+            (_, true) => match chars.next() {
+                Some('n') | Some('N') => unescaped.push(' '),
+                Some(c) => unescaped.push(c),
+                None => {}
+            },
+            (c, false) => unescaped.push(c),
+        }
+    }
+
+    unescaped
+}
+
+fn week_start(date: NaiveDate) -> NaiveDate {
+    date - Duration::days(date.weekday().num_days_from_monday() as i64)
+}
+
+fn months_between(start: NaiveDate, date: NaiveDate) -> i64 {
+    (date.year() - start.year()) as i64 * 12 + date.month() as i64 - start.month() as i64
+}
+
+fn days_in_month(date: NaiveDate) -> u32 {
+    let (year, month) = match date.month() {
+        12 => (date.year() + 1, 1),
+        m => (date.year(), m + 1),
+    };
+
+    NaiveDate::from_ymd(year, month, 1).pred().day()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use indoc::indoc;
+
+    fn starts_on(events: &[CalendarEvent], date: NaiveDate) -> Vec<String> {
+        events_on(events, date)
+            .iter()
+            .map(|e| format!("{} {}", e.start().format("%H%M"), e.description()))
+            .collect()
+    }
+
+    #[test]
+    fn expands_recurrences() {
+        let calendar = parse(indoc! {"
+            BEGIN:VCALENDAR
+            BEGIN:VEVENT
+            UID:standup
+            DTSTART;TZID=Europe/Berlin:20210601T093000
+            RRULE:FREQ=WEEKLY;BYDAY=MO,TU,WE,TH,FR
+            EXDATE;TZID=Europe/Berlin:20210608T093000
+            SUMMARY:Standup
+            END:VEVENT
+            BEGIN:VEVENT
+            UID:standup
+            RECURRENCE-ID;TZID=Europe/Berlin:20210609T093000
+            DTSTART;TZID=Europe/Berlin:20210609T100000
+            SUMMARY:Standup (moved)
+            END:VEVENT
+            BEGIN:VEVENT
+            UID:review
+            DTSTART:20210625T140000
+            RRULE:FREQ=MONTHLY;BYDAY=-1FR;COUNT=2
+            SUMMARY:Monthly review\\, all hands
+            END:VEVENT
+            BEGIN:VEVENT
+            UID:holiday
+            DTSTART;VALUE=DATE:20210607
+            SUMMARY:Holiday
+            END:VEVENT
+            END:VCALENDAR
+        "})
+        .unwrap();
+
+        let day = |d| NaiveDate::from_ymd(2021, 6, d);
+        assert_eq!(starts_on(&calendar, day(1)), vec!["0930 Standup"]);
+        assert!(starts_on(&calendar, day(5)).is_empty());
+        assert_eq!(starts_on(&calendar, day(7)), vec!["0930 Standup"]);
+        assert!(starts_on(&calendar, day(8)).is_empty());
+        assert_eq!(starts_on(&calendar, day(9)), vec!["1000 Standup (moved)"]);
+        assert_eq!(
+            starts_on(&calendar, day(25)),
+            vec!["0930 Standup", "1400 Monthly review, all hands"]
+        );
+
+        let july = NaiveDate::from_ymd(2021, 7, 30);
+        assert_eq!(
+            starts_on(&calendar, july),
+            vec!["0930 Standup", "1400 Monthly review, all hands"]
+        );
+        let august = NaiveDate::from_ymd(2021, 8, 27);
+        assert_eq!(starts_on(&calendar, august), vec!["0930 Standup"]);
+    }
+
+    #[test]
+    fn skips_sub_daily_recurrences() {
+        let calendar = parse(indoc! {"
+            BEGIN:VCALENDAR
+            BEGIN:VEVENT
+            UID:sync
+            DTSTART:20210601T090000
+            RRULE:FREQ=HOURLY;INTERVAL=2
+            SUMMARY:Sync
+            END:VEVENT
+            BEGIN:VEVENT
+            UID:lunch
+            DTSTART:20210601T120000
+            SUMMARY:Lunch
+            END:VEVENT
+            END:VCALENDAR
+        "})
+        .unwrap();
+
+        let day = NaiveDate::from_ymd(2021, 6, 1);
+        assert_eq!(starts_on(&calendar, day), vec!["1200 Lunch"]);
+    }
+
+    #[test]
+    fn rejects_non_ascii_weekdays() {
+        assert!(Recurrence::parse("FREQ=WEEKLY;BYDAY=ÖM").is_err());
+        assert!(Recurrence::parse("FREQ=MONTHLY;BYDAY=1ÖM").is_err());
+    }
+
+    #[test]
+    fn unfolds_lines() {
+        let lines = unfold("SUMMARY:A long\r\n  summary\r\nUID:1\r\n");
+        assert_eq!(lines, vec!["SUMMARY:A long summary", "UID:1"]);
+    }
+}
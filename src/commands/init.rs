@@ -0,0 +1,113 @@
+use std::{fs, io, path::Path};
+
+use anyhow::Context;
+use chrono::NaiveDate;
+
+use planner::{
+    io::{FilePattern, PlanDirectory, PlanFile, PlanStore},
+    plan::format,
+    template::{STARTER_TEMPLATE, TEMPLATE_FILE},
+};
+
+use crate::config::Config;
+
+/// Creates the plan directory, the config file and a starter template, then
+/// optionally imports plan files from another folder.
+pub fn run(plan_dir: &PlanDirectory, import: Option<&Path>) -> anyhow::Result<()> {
+    let path = plan_dir.path();
+
+    fs::create_dir_all(path)
+        .with_context(|| format!("Could not create plan directory {:?}", path))?;
+    println!("Plan directory: {}", path.display());
+
+    if let Some(config_path) = Config::path() {
+        if config_path.exists() {
+            println!("Config file already exists: {}", config_path.display());
+        } else {
+            if let Some(parent) = config_path.parent() {
+                fs::create_dir_all(parent)?;
+            }
+            fs::write(&config_path, Config::default_toml(Some(&path.to_path_buf())))?;
+            println!("Created config file: {}", config_path.display());
+        }
+    }
+
+    let template_path = path.join(TEMPLATE_FILE);
+    if template_path.exists() {
+        println!("Template already exists: {}", template_path.display());
+    } else {
+        plan_dir
+            .store()
+            .write_new(&template_path, STARTER_TEMPLATE)?;
+        println!("Created template: {}", template_path.display());
+    }
+
+    if let Some(source) = import {
+        import_plans(plan_dir, source)?;
+    }
+
+    Ok(())
+}
+
+/// Copies valid plan files from `source` into the plan directory.
+///
+/// A plan file is valid if it parses, and its header date matches the date in
+/// its file name.  Plans are converted if the plan directory keeps them in
+/// another format.  Existing plans are never overwritten.
+fn import_plans(plan_dir: &PlanDirectory, source: &Path) -> anyhow::Result<()> {
+    let source_dir = PlanDirectory::new(source.to_path_buf(), FilePattern::default());
+    let files = source_dir
+        .store()
+        .get_files()
+        .with_context(|| format!("Could not read import directory {:?}", source))?;
+
+    let mut imported = 0;
+    let mut skipped = 0;
+
+    for (file_date, file) in files {
+        let converted = validate(&source_dir, file_date).and_then(|f| convert(plan_dir, &f));
+        match converted {
+            Ok(contents) => match plan_dir.store().create(file_date, &contents) {
+                Ok(()) => imported += 1,
+                Err(e) if e.kind() == io::ErrorKind::AlreadyExists => {
+                    println!("Skipped {}: plan already exists", file.display());
+                    skipped += 1;
+                }
+                Err(e) => return Err(e.into()),
+            },
+            Err(e) => {
+                println!("Skipped {}: {}", file.display(), e);
+                skipped += 1;
+            }
+        }
+    }
+
+    println!("Imported {} plan(s), skipped {}.", imported, skipped);
+    Ok(())
+}
+
+fn validate(source_dir: &PlanDirectory, file_date: NaiveDate) -> anyhow::Result<PlanFile> {
+    let source_file = source_dir.get_plan(file_date)?;
+    let plan_date = source_file.plan().date();
+
+    if file_date != plan_date {
+        anyhow::bail!(
+            "header date {} doesn't match file name date {}",
+            plan_date.format("%m/%d/%Y"),
+            file_date.format("%m/%d/%Y")
+        );
+    }
+
+    Ok(source_file)
+}
+
+/// Gets the contents of an imported plan in the plan directory's format.
+fn convert(plan_dir: &PlanDirectory, source_file: &PlanFile) -> anyhow::Result<String> {
+    let format = format::for_path(&plan_dir.store().path(source_file.date()));
+
+    if format.name() == source_file.format().name() {
+        Ok(fs::read_to_string(source_file.path())?)
+    } else {
+        Ok(format.serialize(source_file.plan()))
+    }
+}
@@ -0,0 +1,158 @@
+use chrono::{Duration, NaiveDateTime, NaiveTime};
+use planner::{clock::Clock, Plan, PlanDirectory, PlanError, ScheduleSection};
+
+/// Prints the planned event in progress, and the next one.
+pub fn now(plan_dir: &PlanDirectory, clock: Clock, short: bool) -> anyhow::Result<()> {
+    let plan = load_today(plan_dir, clock)?;
+    let output = render_now(plan.as_ref(), clock.now(), clock.day_start(), short);
+    print!("{}", output);
+    Ok(())
+}
+
+/// Prints the next planned event, with a countdown.
+pub fn next(plan_dir: &PlanDirectory, clock: Clock, short: bool) -> anyhow::Result<()> {
+    let plan = load_today(plan_dir, clock)?;
+    let output = render_next(plan.as_ref(), clock.now(), clock.day_start(), short);
+    print!("{}", output);
+    Ok(())
+}
+
+/// Loads today's plan from the cache, without creating it if it's missing.
+fn load_today(plan_dir: &PlanDirectory, clock: Clock) -> anyhow::Result<Option<Plan>> {
+    match plan_dir.get_plan_cached(clock.today()) {
+        Ok(plan) => Ok(Some(plan)),
+        Err(PlanError::Missing(_)) => Ok(None),
+        Err(e) => Err(e.into()),
+    }
+}
+
+fn render_now(
+    plan: Option<&Plan>,
+    now: NaiveDateTime,
+    day_start: NaiveTime,
+    short: bool,
+) -> String {
+    let current = plan.and_then(|p| {
+        let schedule = p.schedule().as_ref()?;
+        schedule.current(ScheduleSection::Planned, p.date(), day_start, now)
+    });
+
+    match (current, short) {
+        (Some((_, span)), true) => format!(
+            "{} ({} left)\n",
+            span.event.description(),
+            format_duration(span.end - now)
+        ),
+        (None, true) => String::new(),
+        (current, false) => {
+            let current = match current {
+                Some((_, span)) => format!(
+                    "{} ({}–{}, {} left)",
+                    span.event.description(),
+                    span.start.format("%H:%M"),
+                    span.end.format("%H:%M"),
+                    format_duration(span.end - now)
+                ),
+                None => "nothing planned".to_string(),
+            };
+            format!(
+                "Now:  {}\nNext: {}",
+                current,
+                render_next(plan, now, day_start, false)
+            )
+        }
+    }
+}
+
+fn render_next(
+    plan: Option<&Plan>,
+    now: NaiveDateTime,
+    day_start: NaiveTime,
+    short: bool,
+) -> String {
+    let next = plan.and_then(|p| {
+        let schedule = p.schedule().as_ref()?;
+        schedule.next(ScheduleSection::Planned, p.date(), day_start, now)
+    });
+
+    match (next, short) {
+        (Some((_, span)), true) => format!(
+            "{} in {}\n",
+            span.event.description(),
+            format_duration(span.start - now)
+        ),
+        (Some((_, span)), false) => format!(
+            "{} at {} (in {})\n",
+            span.event.description(),
+            span.start.format("%H:%M"),
+            format_duration(span.start - now)
+        ),
+        (None, true) => String::new(),
+        (None, false) => "nothing else planned today\n".to_string(),
+    }
+}
+
+/// Formats a duration like `1h 5m`, rounding up to the minute.
+fn format_duration(duration: Duration) -> String {
+    let minutes = (duration.num_seconds() + 59) / 60;
+
+    match (minutes / 60, minutes % 60) {
+        (0, m) => format!("{}m", m),
+        (h, 0) => format!("{}h", h),
+        (h, m) => format!("{}h {}m", h, m),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use indoc::indoc;
+
+    #[test]
+    fn current_and_next_events() {
+        let plan = Plan::from_markdown(indoc! {"
+            # 06/01/2021
+            Tuesday
+
+            ## Tasks
+            - **Work**
+              - [ ] Write report
+
+            ## Schedule
+            - **Planned**
+              - 0930\tStandup
+              - 1000\tFocus time
+              - 1200\tLunch
+            - **Actual**
+        "})
+        .unwrap();
+        let date = plan.date();
+        let plan = Some(&plan);
+        let midnight = NaiveTime::from_hms(0, 0, 0);
+        let time = date.and_hms(9, 48, 30);
+
+        assert_eq!(
+            render_now(plan, time, midnight, true),
+            "Standup (12m left)\n"
+        );
+        assert_eq!(
+            render_next(plan, time, midnight, true),
+            "Focus time in 12m\n"
+        );
+        assert_eq!(
+            render_now(plan, time, midnight, false),
+            indoc! {"
+                Now:  Standup (09:30–10:00, 12m left)
+                Next: Focus time at 10:00 (in 12m)
+            "}
+        );
+
+        let time = date.and_hms(10, 15, 0);
+        assert_eq!(render_next(plan, time, midnight, true), "Lunch in 1h 45m\n");
+
+        let time = date.and_hms(13, 0, 0);
+        assert_eq!(render_now(plan, time, midnight, true), "");
+        assert_eq!(render_next(plan, time, midnight, true), "");
+        assert_eq!(render_now(None, time, midnight, true), "");
+    }
+}
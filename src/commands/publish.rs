@@ -0,0 +1,407 @@
+use std::{collections::BTreeMap, fmt::Write, fs, path::Path};
+
+use anyhow::Context;
+use chrono::{Datelike, Duration, IsoWeek, NaiveDate, NaiveTime, Weekday};
+use planner::{Plan, PlanDirectory, ScheduleSection, TaskStatus};
+
+use super::export::load_plans;
+
+const STYLE_FILE: &str = "style.css";
+const STYLE: &str = "\
+body { font-family: sans-serif; max-width: 60em; margin: 2em auto; padding: 0 1em; color: #222; }
+a { color: #0b5cad; }
+nav { display: flex; justify-content: space-between; margin: 1em 0; }
+table.month { border-collapse: collapse; margin-bottom: 2em; }
+table.month th, table.month td { width: 3em; height: 2em; text-align: center; border: 1px solid #ddd; }
+table.month td.plan { background: #eef5fc; font-weight: bold; }
+.columns { display: flex; gap: 2em; }
+.columns section { flex: 1; }
+ul.tasks { list-style: none; padding-left: 0; }
+li.complete { color: #888; text-decoration: line-through; }
+ol.timeline { list-style: none; padding-left: 0; border-left: 3px solid #0b5cad; }
+ol.timeline li { padding: 0.3em 0 0.3em 1em; }
+ol.timeline time { display: inline-block; width: 7em; color: #555; font-family: monospace; }
+";
+
+/// Renders every plan into a static HTML site: a calendar index, a page per
+/// day and a summary per week.  Plans are published under the date of their
+/// file, even if their header says otherwise, and their schedules are laid
+/// out over the plan's day, which starts at `day_start`.
+pub fn run(plan_dir: &PlanDirectory, out: &Path, day_start: NaiveTime) -> anyhow::Result<()> {
+    let mut plans: BTreeMap<NaiveDate, Plan> = BTreeMap::new();
+    for (date, plan) in load_plans(plan_dir, None, None)? {
+        if plan.date() != date {
+            eprintln!(
+                "The plan for {} is dated {} in its header; run `planner doctor` to fix it.",
+                date,
+                plan.date()
+            );
+        }
+        plans.insert(date, plan);
+    }
+
+    for dir in &[out.to_path_buf(), out.join("days"), out.join("weeks")] {
+        fs::create_dir_all(dir)
+            .with_context(|| format!("Could not create site directory {:?}", dir))?;
+    }
+
+    fs::write(out.join(STYLE_FILE), STYLE)?;
+    fs::write(out.join("index.html"), render_index(&plans))?;
+
+    let dates: Vec<NaiveDate> = plans.keys().copied().collect();
+    for (i, (&date, plan)) in plans.iter().enumerate() {
+        let previous = i.checked_sub(1).map(|i| dates[i]);
+        let next = dates.get(i + 1).copied();
+        fs::write(
+            out.join(day_path(date)),
+            render_day(date, plan, day_start, previous, next),
+        )?;
+    }
+
+    let mut weeks: BTreeMap<IsoWeek, Vec<(NaiveDate, &Plan)>> = BTreeMap::new();
+    for (&date, plan) in &plans {
+        weeks.entry(date.iso_week()).or_default().push((date, plan));
+    }
+    let week_keys: Vec<IsoWeek> = weeks.keys().copied().collect();
+    for (i, (week, plans)) in weeks.iter().enumerate() {
+        let previous = i.checked_sub(1).map(|i| week_keys[i]);
+        let next = week_keys.get(i + 1).copied();
+        fs::write(
+            out.join(week_path(*week)),
+            render_week(*week, plans, previous, next),
+        )?;
+    }
+
+    println!(
+        "Published {} day(s) and {} week(s) to {}.",
+        plans.len(),
+        weeks.len(),
+        out.display()
+    );
+    Ok(())
+}
+
+fn day_path(date: NaiveDate) -> String {
+    format!("days/{}.html", date.format("%Y-%m-%d"))
+}
+
+fn week_path(week: IsoWeek) -> String {
+    format!("weeks/{}-W{:02}.html", week.year(), week.week())
+}
+
+fn week_title(week: IsoWeek) -> String {
+    let monday = NaiveDate::from_isoywd(week.year(), week.week(), Weekday::Mon);
+    format!(
+        "Week {} of {} ({} – {})",
+        week.week(),
+        week.year(),
+        monday.format("%b %-d"),
+        (monday + Duration::days(6)).format("%b %-d")
+    )
+}
+
+/// Renders the calendar index, with the newest month first.
+fn render_index(plans: &BTreeMap<NaiveDate, Plan>) -> String {
+    let mut months: Vec<NaiveDate> = plans
+        .keys()
+        .map(|d| NaiveDate::from_ymd(d.year(), d.month(), 1))
+        .collect();
+    months.dedup();
+
+    let mut body = String::new();
+    writeln!(body, "<h1>Plans</h1>").unwrap();
+    if plans.is_empty() {
+        writeln!(body, "<p>No plans yet.</p>").unwrap();
+    }
+
+    for month in months.iter().rev() {
+        writeln!(body, "<h2>{}</h2>", month.format("%B %Y")).unwrap();
+        writeln!(body, "<table class=\"month\">").unwrap();
+        writeln!(
+            body,
+            "<tr><th>Week</th><th>Mon</th><th>Tue</th><th>Wed</th><th>Thu</th><th>Fri</th><th>Sat</th><th>Sun</th></tr>"
+        )
+        .unwrap();
+
+        let mut day = *month - Duration::days(month.weekday().num_days_from_monday() as i64);
+        while day.month() == month.month() || day < *month {
+            let week = day.iso_week();
+            let has_plans = (0..7).any(|i| plans.contains_key(&(day + Duration::days(i))));
+            if has_plans {
+                write!(
+                    body,
+                    "<tr><th><a href=\"{}\">{}</a></th>",
+                    week_path(week),
+                    week.week()
+                )
+                .unwrap();
+            } else {
+                write!(body, "<tr><th>{}</th>", week.week()).unwrap();
+            }
+
+            for _ in 0..7 {
+                if day.month() != month.month() {
+                    write!(body, "<td></td>").unwrap();
+                } else if plans.contains_key(&day) {
+                    write!(
+                        body,
+                        "<td class=\"plan\"><a href=\"{}\">{}</a></td>",
+                        day_path(day),
+                        day.day()
+                    )
+                    .unwrap();
+                } else {
+                    write!(body, "<td>{}</td>", day.day()).unwrap();
+                }
+                day = day.succ();
+            }
+            writeln!(body, "</tr>").unwrap();
+        }
+        writeln!(body, "</table>").unwrap();
+    }
+
+    page("Plans", "", &body)
+}
+
+fn render_day(
+    date: NaiveDate,
+    plan: &Plan,
+    day_start: NaiveTime,
+    previous: Option<NaiveDate>,
+    next: Option<NaiveDate>,
+) -> String {
+    let mut body = String::new();
+
+    let label = |d: NaiveDate| d.format("%a %-d %b").to_string();
+    let previous = match previous {
+        Some(d) => format!("<a href=\"../{}\">← {}</a>", day_path(d), label(d)),
+        None => "<span></span>".to_string(),
+    };
+    let next = match next {
+        Some(d) => format!("<a href=\"../{}\">{} →</a>", day_path(d), label(d)),
+        None => "<span></span>".to_string(),
+    };
+    writeln!(
+        body,
+        "<nav>{}<span><a href=\"../index.html\">Calendar</a> · <a href=\"../{}\">Week {}</a></span>{}</nav>",
+        previous,
+        week_path(date.iso_week()),
+        date.iso_week().week(),
+        next
+    )
+    .unwrap();
+    writeln!(
+        body,
+        "<h1>{}, {}</h1>",
+        plan.day(),
+        date.format("%B %-d, %Y")
+    )
+    .unwrap();
+
+    writeln!(body, "<div class=\"columns\">").unwrap();
+    writeln!(body, "<section><h2>Tasks</h2>").unwrap();
+    let categories = plan.tasks().iter().flat_map(|t| t.categories());
+    for category in categories {
+        writeln!(
+            body,
+            "<h3>{}</h3><ul class=\"tasks\">",
+            escape(category.name())
+        )
+        .unwrap();
+        for task in category.tasks() {
+            let (class, mark) = match task.status() {
+                TaskStatus::Complete => ("complete", "☑"),
+                TaskStatus::Incomplete => ("incomplete", "☐"),
+            };
+            writeln!(
+                body,
+                "<li class=\"{}\">{} {}</li>",
+                class,
+                mark,
+                escape(task.description())
+            )
+            .unwrap();
+        }
+        writeln!(body, "</ul>").unwrap();
+    }
+    writeln!(body, "</section>").unwrap();
+
+    if let Some(schedule) = plan.schedule() {
+        for &(section, title) in &[
+            (ScheduleSection::Planned, "Planned"),
+            (ScheduleSection::Actual, "Actual"),
+        ] {
+            writeln!(body, "<section><h2>{}</h2><ol class=\"timeline\">", title).unwrap();
+            for span in schedule.spans(section, date, day_start) {
+                writeln!(
+                    body,
+                    "<li><time>{}–{}</time> {}</li>",
+                    span.start.format("%H:%M"),
+                    span.end.format("%H:%M"),
+                    escape(span.event.description())
+                )
+                .unwrap();
+            }
+            writeln!(body, "</ol></section>").unwrap();
+        }
+    }
+    writeln!(body, "</div>").unwrap();
+
+    page(&date.format("%A, %B %-d, %Y").to_string(), "../", &body)
+}
+
+fn render_week(
+    week: IsoWeek,
+    plans: &[(NaiveDate, &Plan)],
+    previous: Option<IsoWeek>,
+    next: Option<IsoWeek>,
+) -> String {
+    let mut body = String::new();
+
+    let link = |w: Option<IsoWeek>, label: &str| match w {
+        Some(w) => format!("<a href=\"../{}\">{}</a>", week_path(w), label),
+        None => "<span></span>".to_string(),
+    };
+    writeln!(
+        body,
+        "<nav>{}<a href=\"../index.html\">Calendar</a>{}</nav>",
+        link(previous, "← Previous week"),
+        link(next, "Next week →")
+    )
+    .unwrap();
+    writeln!(body, "<h1>{}</h1>", week_title(week)).unwrap();
+
+    writeln!(body, "<table>").unwrap();
+    writeln!(
+        body,
+        "<tr><th>Day</th><th>Tasks done</th><th>Planned events</th><th>Actual events</th></tr>"
+    )
+    .unwrap();
+
+    let (mut done, mut total) = (0, 0);
+    for &(date, plan) in plans {
+        let tasks: Vec<_> = plan
+            .tasks()
+            .iter()
+            .flat_map(|t| t.categories())
+            .flat_map(|c| c.tasks())
+            .collect();
+        let complete = tasks
+            .iter()
+            .filter(|t| t.status() == &TaskStatus::Complete)
+            .count();
+        done += complete;
+        total += tasks.len();
+
+        let (planned, actual) = match plan.schedule() {
+            Some(s) => (s.planned().len(), s.actual().len()),
+            None => (0, 0),
+        };
+
+        writeln!(
+            body,
+            "<tr><td><a href=\"../{}\">{}</a></td><td>{} / {}</td><td>{}</td><td>{}</td></tr>",
+            day_path(date),
+            date.format("%A %-d %b"),
+            complete,
+            tasks.len(),
+            planned,
+            actual
+        )
+        .unwrap();
+    }
+    writeln!(body, "</table>").unwrap();
+    writeln!(
+        body,
+        "<p>{} of {} task(s) done over {} day(s).</p>",
+        done,
+        total,
+        plans.len()
+    )
+    .unwrap();
+
+    page(&week_title(week), "../", &body)
+}
+
+/// Wraps a page body in a complete HTML document.
+fn page(title: &str, root: &str, body: &str) -> String {
+    format!(
+        "<!DOCTYPE html>\n\
+         <html lang=\"en\">\n\
+         <head>\n\
+         <meta charset=\"utf-8\">\n\
+         <title>{}</title>\n\
+         <link rel=\"stylesheet\" href=\"{}{}\">\n\
+         </head>\n\
+         <body>\n\
+         {}\
+         </body>\n\
+         </html>\n",
+        escape(title),
+        root,
+        STYLE_FILE,
+        body
+    )
+}
+
+fn escape(text: &str) -> String {
+    text.replace('&', "&amp;")
+        .replace('<', "&lt;")
+        .replace('>', "&gt;")
+        .replace('"', "&quot;")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use planner::io::FilePattern;
+
+    #[test]
+    fn publishes_site() {
+        let dir = tempfile::tempdir().unwrap();
+        let plan_dir = PlanDirectory::new(dir.path().join("plans"), FilePattern::default());
+        let monday = NaiveDate::from_ymd(2021, 5, 31);
+        plan_dir.create_plan(monday).unwrap();
+        plan_dir.create_plan(monday.succ()).unwrap();
+
+        let out = dir.path().join("site");
+        let midnight = NaiveTime::from_hms(0, 0, 0);
+        run(&plan_dir, &out, midnight).unwrap();
+
+        let index = fs::read_to_string(out.join("index.html")).unwrap();
+        assert!(index.contains("<h2>June 2021</h2>"));
+        assert!(index.contains("<a href=\"days/2021-06-01.html\">1</a>"));
+        assert!(index.contains("<a href=\"weeks/2021-W22.html\">22</a>"));
+
+        let day = fs::read_to_string(out.join("days/2021-05-31.html")).unwrap();
+        assert!(day.contains("<h1>Monday, May 31, 2021</h1>"));
+        assert!(day.contains("<a href=\"../days/2021-06-01.html\">Tue 1 Jun →</a>"));
+        assert!(day.contains("<li><time>09:00–12:00</time> Start the day</li>"));
+        assert!(day.contains("<li><time>12:00–12:30</time> Lunch</li>"));
+
+        let week = fs::read_to_string(out.join("weeks/2021-W22.html")).unwrap();
+        assert!(week.contains("over 2 day(s)"));
+    }
+
+    #[test]
+    fn publishes_plans_under_their_file_date() {
+        let dir = tempfile::tempdir().unwrap();
+        let plan_dir = PlanDirectory::new(dir.path().join("plans"), FilePattern::default());
+        let monday = NaiveDate::from_ymd(2021, 5, 31);
+        plan_dir.create_plan(monday).unwrap();
+        fs::copy(
+            plan_dir.get_plan_path(monday),
+            plan_dir.get_plan_path(monday.succ()),
+        )
+        .unwrap();
+
+        let out = dir.path().join("site");
+        let midnight = NaiveTime::from_hms(0, 0, 0);
+        run(&plan_dir, &out, midnight).unwrap();
+
+        assert!(out.join("days/2021-05-31.html").exists());
+        assert!(out.join("days/2021-06-01.html").exists());
+        let week = fs::read_to_string(out.join("weeks/2021-W22.html")).unwrap();
+        assert!(week.contains("over 2 day(s)"));
+    }
+}
@@ -0,0 +1,180 @@
+use std::{
+    fs,
+    io::{self, Read},
+    path::Path,
+};
+
+use anyhow::Context;
+use chrono::NaiveDate;
+use planner::{
+    io::PlanStore,
+    plan::{
+        taskwarrior::{self, from_taskwarrior, to_taskwarrior, TaskwarriorStatus},
+        todotxt::DEFAULT_CATEGORY,
+    },
+    PlanDirectory, Task, TaskList, TaskStatus,
+};
+
+use super::todotxt::is_same_category;
+
+/// Prints the open tasks of the plan for a date as Taskwarrior JSON, for
+/// `task import`, along with the Taskwarrior tasks completed in the plan.
+pub fn export(plan_dir: &PlanDirectory, date: NaiveDate) -> anyhow::Result<()> {
+    let file = plan_dir.get_plan(date)?;
+
+    let tasks = match file.plan().tasks() {
+        Some(tasks) => to_taskwarrior(tasks),
+        None => Vec::new(),
+    };
+    println!("{}", serde_json::to_string_pretty(&tasks)?);
+    Ok(())
+}
+
+/// Syncs the plan for a date with the output of `task export` (or stdin, if
+/// the path is `-`).  Tasks the plan already has, by UUID or description, are
+/// updated, and new pending tasks are added.
+pub fn import(plan_dir: &PlanDirectory, json: &Path, date: NaiveDate) -> anyhow::Result<()> {
+    let text = if json == Path::new("-") {
+        let mut text = String::new();
+        io::stdin().read_to_string(&mut text)?;
+        text
+    } else {
+        fs::read_to_string(json)
+            .with_context(|| format!("Could not read Taskwarrior export {:?}", json))?
+    };
+    let imported = taskwarrior::parse(&text).context("Invalid Taskwarrior export")?;
+
+    let mut file = plan_dir.get_or_create_plan(date)?;
+
+    let format = file.format();
+    let mut contents = plan_dir.store().read(file.date())?;
+    let mut plan = file.plan().clone();
+    let tasks = plan
+        .tasks_mut()
+        .get_or_insert_with(|| TaskList::new(Vec::new()));
+    let (mut added, mut updated) = (0, 0);
+
+    for tw_task in imported
+        .iter()
+        .filter(|t| t.status != TaskwarriorStatus::Deleted)
+    {
+        let (project, mut task) = from_taskwarrior(tw_task);
+
+        match find_task(tasks, project, &task) {
+            Some((category, existing)) => {
+                // Tasks completed in the plan stay completed.
+                if existing.status() == &TaskStatus::Complete {
+                    task.set_status(TaskStatus::Complete);
+                }
+                if existing.description() == task.description()
+                    && existing.status() == task.status()
+                {
+                    continue;
+                }
+
+                contents = format.replace_task(&contents, &category, existing, &task)?;
+                *existing = task;
+                updated += 1;
+            }
+            None if task.status() == &TaskStatus::Incomplete => {
+                let project = project.unwrap_or(DEFAULT_CATEGORY);
+                let name = tasks
+                    .categories()
+                    .iter()
+                    .map(|c| c.name())
+                    .find(|n| is_same_category(n, project))
+                    .unwrap_or(project)
+                    .to_string();
+
+                contents = format.insert_tasks(&contents, &name, &[&task])?;
+                tasks.category_mut(&name).tasks_mut().push(task);
+                added += 1;
+            }
+            None => {}
+        }
+    }
+
+    if added + updated > 0 {
+        file.write(plan_dir, &contents)?;
+    }
+
+    println!(
+        "Added {} and updated {} task(s) in {}.",
+        added,
+        updated,
+        file.path().display()
+    );
+    Ok(())
+}
+
+/// Finds the plan's copy of an imported task, and its category: the task with
+/// the same UUID, or else a task without a UUID that has the same
+/// description, in the same category.
+fn find_task<'a>(
+    tasks: &'a mut TaskList,
+    project: Option<&str>,
+    task: &Task,
+) -> Option<(String, &'a mut Task)> {
+    let uuid = task.get_metadata("uuid");
+    let description = without_uuid(task.description());
+
+    let position = tasks.categories().iter().enumerate().find_map(|(i, c)| {
+        c.tasks()
+            .iter()
+            .position(|t| uuid.is_some() && t.get_metadata("uuid") == uuid)
+            .map(|j| (i, j))
+    });
+    let position = position.or_else(|| {
+        tasks.categories().iter().enumerate().find_map(|(i, c)| {
+            if project.is_some_and(|p| !is_same_category(c.name(), p)) {
+                return None;
+            }
+            c.tasks()
+                .iter()
+                .position(|t| {
+                    t.get_metadata("uuid").is_none()
+                        && without_uuid(t.description()).eq_ignore_ascii_case(&description)
+                })
+                .map(|j| (i, j))
+        })
+    });
+
+    let (i, j) = position?;
+    let category = &mut tasks.categories_mut()[i];
+    Some((category.name().to_string(), &mut category.tasks_mut()[j]))
+}
+
+fn without_uuid(description: &str) -> String {
+    description
+        .split_whitespace()
+        .filter(|w| !w.starts_with("uuid:"))
+        .collect::<Vec<_>>()
+        .join(" ")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use planner::TaskCategory;
+
+    #[test]
+    fn matches_tasks_by_uuid_or_description() {
+        let mut tasks = TaskList::new(vec![TaskCategory::new(
+            "Work",
+            vec![
+                Task::new("Write report uuid:1234", TaskStatus::Incomplete),
+                Task::new("Call the bank", TaskStatus::Incomplete),
+            ],
+        )]);
+
+        let renamed = Task::new("Write the report uuid:1234", TaskStatus::Complete);
+        let (category, found) = find_task(&mut tasks, Some("Home"), &renamed).unwrap();
+        assert_eq!(category, "Work");
+        assert_eq!(found.description(), "Write report uuid:1234");
+
+        let call = Task::new("call the bank uuid:5678", TaskStatus::Incomplete);
+        let (_, found) = find_task(&mut tasks, Some("work"), &call).unwrap();
+        assert_eq!(found.description(), "Call the bank");
+        assert!(find_task(&mut tasks, Some("Home"), &call).is_none());
+    }
+}
@@ -0,0 +1,152 @@
+use std::fmt::Write;
+
+use chrono::NaiveDate;
+
+use planner::{Plan, PlanDirectory, PlanError, TaskStatus};
+
+use crate::config::Config;
+
+/// Prints the plans for `today` from every configured workspace.
+pub fn run_all(config: &Config, today: NaiveDate) -> anyhow::Result<()> {
+    if config.workspaces.is_empty() {
+        anyhow::bail!("No workspaces are configured.");
+    }
+
+    let pattern = config.file_pattern()?;
+    let mut plans = Vec::new();
+
+    for (name, dir) in &config.workspaces {
+        let plan_dir = PlanDirectory::new(dir.to_owned(), pattern.clone());
+        let plan = match plan_dir.get_plan(today) {
+            Ok(file) => Some(file.plan().clone()),
+            Err(PlanError::Missing(_)) => None,
+            Err(e) => {
+                eprintln!("{}: {:#}", name, anyhow::Error::from(e));
+                None
+            }
+        };
+        plans.push((name.as_str(), plan));
+    }
+
+    print!("{}", render(today, &plans));
+    Ok(())
+}
+
+/// Renders several plans for the same day as one markdown document, with task
+/// categories grouped by workspace and the planned events merged by start time.
+fn render(date: NaiveDate, plans: &[(&str, Option<Plan>)]) -> String {
+    let mut md = String::new();
+
+    writeln!(md, "# {}", date.format("%m/%d/%Y")).unwrap();
+    writeln!(md, "{}", date.format("%A")).unwrap();
+    writeln!(md).unwrap();
+
+    writeln!(md, "## Tasks").unwrap();
+    for (workspace, plan) in plans {
+        writeln!(md, "- **{}**", workspace).unwrap();
+
+        let tasks = match plan {
+            Some(p) => p.tasks(),
+            None => {
+                writeln!(md, "  - No plan for today").unwrap();
+                continue;
+            }
+        };
+
+        for category in tasks.iter().flat_map(|t| t.categories()) {
+            writeln!(md, "  - **{}**", category.name()).unwrap();
+            for task in category.tasks() {
+                let check = match task.status() {
+                    TaskStatus::Complete => "x",
+                    TaskStatus::Incomplete => " ",
+                };
+                writeln!(md, "    - [{}] {}", check, task.description()).unwrap();
+            }
+        }
+    }
+    writeln!(md).unwrap();
+
+    let mut events: Vec<_> = plans
+        .iter()
+        .filter_map(|(workspace, plan)| Some((workspace, plan.as_ref()?.schedule().as_ref()?)))
+        .flat_map(|(workspace, schedule)| schedule.planned().iter().map(move |e| (workspace, e)))
+        .collect();
+    events.sort_by_key(|(_, e)| *e.start());
+
+    writeln!(md, "## Schedule").unwrap();
+    writeln!(md, "- **Planned**").unwrap();
+    for (workspace, event) in events {
+        let time = event.start().format("%H%M").to_string();
+        writeln!(md, "  - {}\t[{}] {}", time, workspace, event.description()).unwrap();
+    }
+
+    md
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use indoc::indoc;
+
+    #[test]
+    fn merges_workspaces() {
+        let work = Plan::from_markdown(indoc! {"
+            # 01/01/2000
+            Saturday
+
+            ## Tasks
+            - **Work**
+              - [ ] Ship it
+
+            ## Schedule
+            - **Planned**
+              - 1000\tStandup
+        "})
+        .unwrap();
+        let personal = Plan::from_markdown(indoc! {"
+            # 01/01/2000
+            Saturday
+
+            ## Tasks
+            - **Home**
+              - [x] Laundry
+
+            ## Schedule
+            - **Planned**
+              - 0800\tRun
+        "})
+        .unwrap();
+
+        let md = render(
+            NaiveDate::from_ymd(2000, 1, 1),
+            &[
+                ("personal", Some(personal)),
+                ("play", None),
+                ("work", Some(work)),
+            ],
+        );
+
+        assert_eq!(
+            md,
+            indoc! {"
+                # 01/01/2000
+                Saturday
+
+                ## Tasks
+                - **personal**
+                  - **Home**
+                    - [x] Laundry
+                - **play**
+                  - No plan for today
+                - **work**
+                  - **Work**
+                    - [ ] Ship it
+
+                ## Schedule
+                - **Planned**
+                  - 0800\t[personal] Run
+                  - 1000\t[work] Standup
+            "}
+        );
+    }
+}
@@ -0,0 +1,80 @@
+use std::{fs, path::Path};
+
+use anyhow::Context;
+use chrono::NaiveDate;
+use planner::{
+    io::PlanStore,
+    plan::todotxt::{from_todotxt, to_todotxt},
+    PlanDirectory, Task, TaskList,
+};
+
+/// Prints the tasks of the plan for a date in the todo.txt format.
+pub fn export(plan_dir: &PlanDirectory, date: NaiveDate) -> anyhow::Result<()> {
+    let file = plan_dir.get_plan(date)?;
+
+    if let Some(tasks) = file.plan().tasks() {
+        print!("{}", to_todotxt(tasks, file.plan().date()));
+    }
+    Ok(())
+}
+
+/// Adds the tasks from a todo.txt file to the plan for a date, skipping tasks
+/// the plan already has.
+pub fn import(plan_dir: &PlanDirectory, todotxt: &Path, date: NaiveDate) -> anyhow::Result<()> {
+    let text = fs::read_to_string(todotxt)
+        .with_context(|| format!("Could not read todo.txt file {:?}", todotxt))?;
+    let imported = from_todotxt(&text);
+
+    let mut file = plan_dir.get_or_create_plan(date)?;
+    let existing = file
+        .plan()
+        .tasks()
+        .clone()
+        .unwrap_or_else(|| TaskList::new(Vec::new()));
+    let mut contents = plan_dir.store().read(file.date())?;
+    let mut added = 0;
+
+    for category in imported.categories() {
+        let name = existing
+            .categories()
+            .iter()
+            .map(|c| c.name())
+            .find(|n| is_same_category(n, category.name()))
+            .unwrap_or_else(|| category.name());
+        let tasks: Vec<&Task> = category
+            .tasks()
+            .iter()
+            .filter(|t| !has_task(&existing, name, t))
+            .collect();
+
+        if tasks.is_empty() {
+            continue;
+        }
+
+        contents = file.format().insert_tasks(&contents, name, &tasks)?;
+        added += tasks.len();
+    }
+
+    if added > 0 {
+        file.write(plan_dir, &contents)?;
+    }
+
+    println!("Imported {} task(s) into {}.", added, file.path().display());
+    Ok(())
+}
+
+/// Compares category names, ignoring case and the `_` that todo.txt uses in
+/// place of spaces.
+pub(crate) fn is_same_category(a: &str, b: &str) -> bool {
+    let normalize = |s: &str| s.replace('_', " ").trim().to_lowercase();
+    normalize(a) == normalize(b)
+}
+
+fn has_task(tasks: &TaskList, category: &str, task: &Task) -> bool {
+    tasks
+        .categories()
+        .iter()
+        .filter(|c| is_same_category(c.name(), category))
+        .flat_map(|c| c.tasks())
+        .any(|t| t.description().eq_ignore_ascii_case(task.description()))
+}
@@ -0,0 +1,84 @@
+mod terminal;
+mod timeline;
+
+use std::{
+    env,
+    io::{self, IsTerminal},
+};
+
+use chrono::NaiveTime;
+use planner::{clock::Clock, Plan};
+
+use crate::cli::{Format, ViewOptions};
+
+/// Prints a plan in the given format, or its schedule as a timeline.
+pub fn run(plan: &Plan, options: &ViewOptions, clock: Clock) -> anyhow::Result<()> {
+    // Only today's plan has a current event.
+    let now = Some(clock.now()).filter(|&now| clock.plan_date(now) == plan.date());
+    let day_start = clock.day_start();
+
+    let output = match options.format {
+        _ if options.timeline => timeline::render(plan, now, day_start, use_color()),
+        Format::Text => terminal::render(plan, now, day_start, use_color()),
+        format => render(plan, format)?,
+    };
+
+    print!("{}", output);
+    Ok(())
+}
+
+/// Colors are used on terminals, unless turned off with `NO_COLOR`
+/// (https://no-color.org).
+fn use_color() -> bool {
+    let no_color = env::var_os("NO_COLOR").is_some_and(|v| !v.is_empty());
+    !no_color && io::stdout().is_terminal()
+}
+
+fn render(plan: &Plan, format: Format) -> anyhow::Result<String> {
+    Ok(match format {
+        Format::Text => terminal::render(plan, None, NaiveTime::from_hms(0, 0, 0), false),
+        Format::Markdown => plan.to_markdown(),
+        Format::Json => serde_json::to_string_pretty(plan)? + "\n",
+        Format::Yaml => serde_yaml::to_string(plan)?,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use indoc::indoc;
+
+    #[test]
+    fn renders_json() {
+        let plan = Plan::from_markdown(indoc! {"
+            # 06/01/2021
+            Tuesday
+
+            ## Tasks
+            - **Work**
+              - [x] Write report
+
+            ## Schedule
+            - **Planned**
+              - 0930\tStandup
+            - **Actual**
+        "})
+        .unwrap();
+
+        let json: serde_json::Value =
+            serde_json::from_str(&render(&plan, Format::Json).unwrap()).unwrap();
+        assert_eq!(json["date"], "2021-06-01");
+        assert_eq!(
+            json["tasks"]["categories"][0]["tasks"][0]["status"],
+            "complete"
+        );
+        assert_eq!(json["schedule"]["planned"][0]["start"], "09:30:00");
+
+        let yaml = render(&plan, Format::Yaml).unwrap();
+        let parsed: Plan = serde_yaml::from_str(&yaml).unwrap();
+        assert_eq!(
+            serde_json::to_value(&parsed).unwrap(),
+            serde_json::to_value(&plan).unwrap()
+        );
+    }
+}
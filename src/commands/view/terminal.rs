@@ -0,0 +1,207 @@
+use std::fmt::Write;
+
+use chrono::{NaiveDateTime, NaiveTime};
+use planner::{Plan, ScheduleSection, Task, TaskStatus};
+
+pub(super) const BOLD: &str = "1";
+pub(super) const DIM: &str = "2";
+pub(super) const GREEN: &str = "32";
+pub(super) const YELLOW: &str = "33";
+pub(super) const CYAN: &str = "36";
+
+/// Renders a plan for the terminal: tasks grouped under their categories and
+/// checked off with glyphs, the schedule with aligned times, and the notes.
+/// If `now` is given, the current or next planned event is highlighted.  The
+/// plan's day runs from `day_start` until `day_start` the next day.
+pub fn render(
+    plan: &Plan,
+    now: Option<NaiveDateTime>,
+    day_start: NaiveTime,
+    color: bool,
+) -> String {
+    let style = Style { color };
+    let mut out = String::new();
+
+    writeln!(
+        out,
+        "{}",
+        style.paint(&plan.date().format("%A, %B %-d, %Y").to_string(), BOLD)
+    )
+    .unwrap();
+
+    if let Some(tasks) = plan.tasks() {
+        for category in tasks.categories() {
+            writeln!(out).unwrap();
+            writeln!(
+                out,
+                "{}",
+                style.paint(category.name(), &format!("{};{}", BOLD, CYAN))
+            )
+            .unwrap();
+            for task in category.tasks() {
+                writeln!(out, "  {}", style.task(task)).unwrap();
+            }
+        }
+    }
+
+    if let Some(schedule) = plan.schedule() {
+        // The event that is on now, or else the next one.
+        let highlighted = now.and_then(|now| {
+            let planned = ScheduleSection::Planned;
+            match schedule.current(planned, plan.date(), day_start, now) {
+                Some((i, _)) => Some((i, "now")),
+                None => schedule
+                    .next(planned, plan.date(), day_start, now)
+                    .map(|(i, _)| (i, "next")),
+            }
+        });
+
+        for &(section, name) in &[
+            (ScheduleSection::Planned, "Planned"),
+            (ScheduleSection::Actual, "Actual"),
+        ] {
+            writeln!(out).unwrap();
+            writeln!(out, "{}", style.paint(name, &format!("{};{}", BOLD, CYAN))).unwrap();
+
+            // Spans also decide the highlighted event, so every event, the
+            // last one too, shows the end it's highlighted until.
+            let spans = schedule.spans(section, plan.date(), day_start);
+            for (i, span) in spans.into_iter().enumerate() {
+                let times = format!(
+                    "{}–{}",
+                    format_time(&span.start.time()),
+                    format_time(&span.end.time())
+                );
+                let line = format!("{:<11}  {}", times, span.event.description());
+
+                match highlighted {
+                    Some((index, label)) if section == ScheduleSection::Planned && index == i => {
+                        let line = format!("{} ({})", line, label);
+                        writeln!(
+                            out,
+                            "▶ {}",
+                            style.paint(&line, &format!("{};{}", BOLD, YELLOW))
+                        )
+                        .unwrap();
+                    }
+                    _ => writeln!(out, "  {}", line).unwrap(),
+                }
+            }
+        }
+    }
+
+    if !plan.notes().is_empty() {
+        writeln!(out).unwrap();
+        writeln!(
+            out,
+            "{}",
+            style.paint("Notes", &format!("{};{}", BOLD, CYAN))
+        )
+        .unwrap();
+        for line in plan.notes().lines() {
+            writeln!(out, "  {}", line).unwrap();
+        }
+    }
+
+    out
+}
+
+fn format_time(time: &NaiveTime) -> String {
+    time.format("%H:%M").to_string()
+}
+
+/// Wraps text in ANSI escape codes, unless color is turned off.
+pub(super) struct Style {
+    pub(super) color: bool,
+}
+
+impl Style {
+    pub(super) fn paint(&self, text: &str, codes: &str) -> String {
+        if self.color {
+            format!("\x1b[{}m{}\x1b[0m", codes, text)
+        } else {
+            text.to_string()
+        }
+    }
+
+    fn task(&self, task: &Task) -> String {
+        match task.status() {
+            TaskStatus::Complete => format!(
+                "{} {}",
+                self.paint("✓", GREEN),
+                self.paint(task.description(), DIM)
+            ),
+            TaskStatus::Incomplete => {
+                let words: Vec<String> = task
+                    .description()
+                    .split_whitespace()
+                    .map(|w| match w.strip_prefix('#') {
+                        Some(tag) if !tag.is_empty() => self.paint(w, CYAN),
+                        _ => w.to_string(),
+                    })
+                    .collect();
+                format!("☐ {}", words.join(" "))
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use indoc::indoc;
+
+    #[test]
+    fn renders_plan() {
+        let plan = Plan::from_markdown(indoc! {"
+            # 06/01/2021
+            Tuesday
+
+            ## Tasks
+            - **Work**
+              - [x] Write report
+              - [ ] Call the bank #phone
+
+            ## Schedule
+            - **Planned**
+              - 0930\tStandup
+              - 1000\tFocus time
+            - **Actual**
+              - 0945\tStandup
+            ## Notes
+            Remember the milk.
+        "})
+        .unwrap();
+        let date = plan.date();
+        let midnight = NaiveTime::from_hms(0, 0, 0);
+
+        assert_eq!(
+            render(&plan, Some(date.and_hms(9, 40, 0)), midnight, false),
+            indoc! {"
+                Tuesday, June 1, 2021
+
+                Work
+                  ✓ Write report
+                  ☐ Call the bank #phone
+
+                Planned
+                ▶ 09:30–10:00  Standup (now)
+                  10:00–10:30  Focus time
+
+                Actual
+                  09:45–10:15  Standup
+
+                Notes
+                  Remember the milk.
+            "}
+        );
+
+        let late = render(&plan, Some(date.and_hms(10, 15, 0)), midnight, false);
+        assert!(late.contains("▶ 10:00–10:30  Focus time (now)"));
+        let evening = render(&plan, Some(date.and_hms(18, 0, 0)), midnight, false);
+        assert!(!evening.contains('▶'));
+        let morning = render(&plan, Some(date.and_hms(8, 0, 0)), midnight, true);
+        assert!(morning.contains("Standup (next)\x1b[0m"));
+        assert!(morning.contains("\x1b[2mWrite report\x1b[0m"));
+    }
+}
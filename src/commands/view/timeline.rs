@@ -0,0 +1,210 @@
+use std::fmt::Write;
+
+use chrono::{Duration, NaiveDateTime, NaiveTime, Timelike};
+use planner::{plan::schedule::Span, Plan, ScheduleSection};
+
+use super::terminal::{Style, BOLD, CYAN, GREEN, YELLOW};
+
+/// The length of a row of the timeline.
+const SLOT_MINUTES: i64 = 15;
+
+/// The width of the planned and actual columns.
+const COLUMN_WIDTH: usize = 30;
+
+/// Draws the schedule of a plan on a vertical time axis, with a row per 15
+/// minutes, and the planned and actual events side by side.  If `now` is
+/// given, its row is marked.  The axis covers the plan's day, which runs from
+/// `day_start` until `day_start` the next day.
+pub fn render(
+    plan: &Plan,
+    now: Option<NaiveDateTime>,
+    day_start: NaiveTime,
+    color: bool,
+) -> String {
+    let style = Style { color };
+    let mut out = String::new();
+    let day_end = plan.date().succ().and_time(day_start);
+
+    let (planned, actual) = match plan.schedule() {
+        Some(schedule) => (
+            schedule.spans(ScheduleSection::Planned, plan.date(), day_start),
+            schedule.spans(ScheduleSection::Actual, plan.date(), day_start),
+        ),
+        None => (Vec::new(), Vec::new()),
+    };
+
+    let starts = planned.iter().chain(&actual).map(|span| span.start);
+    let ends = planned.iter().chain(&actual).map(|span| span.end);
+    let (first, last) = match (starts.min(), ends.max()) {
+        (Some(first), Some(last)) => (first, last),
+        _ => return "No events.\n".to_string(),
+    };
+
+    // Whole hours, stretched to include the current time.
+    let first = first.min(now.unwrap_or(first));
+    let first = first.date().and_hms(first.hour(), 0, 0);
+    let last = last.max(now.map_or(last, |n| n + Duration::minutes(1)));
+
+    writeln!(
+        out,
+        "{:6}  {} {}",
+        "",
+        style.paint(&format!("{:width$}", "Planned", width = COLUMN_WIDTH), BOLD),
+        style.paint("Actual", BOLD)
+    )
+    .unwrap();
+
+    let mut slot = first;
+    while slot < last && slot < day_end {
+        let slot_end = slot + Duration::minutes(SLOT_MINUTES);
+        let is_now = now.is_some_and(|n| slot <= n && n < slot_end);
+
+        let label = match slot.minute() {
+            0 => slot.format("%H:%M").to_string(),
+            _ => String::new(),
+        };
+        let axis = match (is_now, slot.minute()) {
+            (true, _) => style.paint("●", YELLOW),
+            (false, 0) => "┼".to_string(),
+            (false, _) => "│".to_string(),
+        };
+
+        let planned_cell = cell(&planned, slot, slot_end);
+        let actual_cell = cell(&actual, slot, slot_end);
+        let mut row = format!(
+            "{:>5} {} {} {}",
+            label,
+            axis,
+            style.paint(
+                &format!("{:width$}", planned_cell, width = COLUMN_WIDTH),
+                CYAN
+            ),
+            style.paint(
+                &format!("{:width$}", actual_cell, width = COLUMN_WIDTH),
+                GREEN
+            )
+        );
+
+        if let (true, Some(now)) = (is_now, now) {
+            let marker = format!(" ◀ now {}", now.format("%H:%M"));
+            row.push_str(&style.paint(&marker, &format!("{};{}", BOLD, YELLOW)));
+        }
+
+        writeln!(out, "{}", row.trim_end()).unwrap();
+        slot = slot_end;
+    }
+
+    out
+}
+
+/// Gets the text of a column in a row: the names of the events that start in
+/// the row, or a bar if an earlier event is still going on.
+fn cell(spans: &[Span], slot: NaiveDateTime, slot_end: NaiveDateTime) -> String {
+    let starting: Vec<&str> = spans
+        .iter()
+        .filter(|span| slot <= span.start && span.start < slot_end)
+        .map(|span| span.event.description())
+        .collect();
+
+    if !starting.is_empty() {
+        let text = format!("█ {}", starting.join(", "));
+        return truncate(&text, COLUMN_WIDTH - 1);
+    }
+
+    if spans
+        .iter()
+        .any(|span| span.start < slot && slot < span.end)
+    {
+        "█".to_string()
+    } else {
+        String::new()
+    }
+}
+
+fn truncate(text: &str, width: usize) -> String {
+    if text.chars().count() > width {
+        text.chars().take(width - 1).collect::<String>() + "…"
+    } else {
+        text.to_string()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use indoc::indoc;
+
+    #[test]
+    fn draws_timeline() {
+        let plan = Plan::from_markdown(indoc! {"
+            # 06/01/2021
+            Tuesday
+
+            ## Tasks
+            - **Work**
+              - [ ] Write report
+
+            ## Schedule
+            - **Planned**
+              - 0930\tStandup
+              - 1000\tFocus time
+            - **Actual**
+              - 0945\tStandup
+              - 1015\tFocus time
+        "})
+        .unwrap();
+
+        let date = plan.date();
+        let midnight = NaiveTime::from_hms(0, 0, 0);
+
+        assert_eq!(
+            render(&plan, Some(date.and_hms(10, 5, 0)), midnight, false),
+            indoc! {"
+                        Planned                        Actual
+                09:00 ┼
+                      │
+                      │ █ Standup
+                      │ █                              █ Standup
+                10:00 ● █ Focus time                   █                              ◀ now 10:05
+                      │ █                              █ Focus time
+                      │                                █
+            "}
+        );
+    }
+
+    #[test]
+    fn draws_timeline_through_the_night() {
+        let plan = Plan::from_markdown(indoc! {"
+            # 06/01/2021
+            Tuesday
+
+            ## Tasks
+            - **Home**
+              - [ ] Sleep
+
+            ## Schedule
+            - **Planned**
+              - 2300\tRead
+              - 0045\tSleep
+            - **Actual**
+        "})
+        .unwrap();
+
+        // The day runs until 01:00, so the axis goes past midnight, in order,
+        // and the last event is cut short at the end of the day.
+        assert_eq!(
+            render(&plan, None, NaiveTime::from_hms(1, 0, 0), false),
+            indoc! {"
+                        Planned                        Actual
+                23:00 ┼ █ Read
+                      │ █
+                      │ █
+                      │ █
+                00:00 ┼ █
+                      │ █
+                      │ █
+                      │ █ Sleep
+            "}
+        );
+    }
+}
@@ -0,0 +1,198 @@
+use std::{collections::BTreeMap, fs, path::PathBuf};
+
+use anyhow::Context;
+use chrono::NaiveTime;
+use serde::Deserialize;
+
+use planner::io::FilePattern;
+
+const CONFIG_DIR: &str = "planner";
+const CONFIG_FILE: &str = "config.toml";
+
+/// User configuration, read from `config.toml` in the platform config
+/// directory (e.g. `~/.config/planner/config.toml`).
+#[derive(Debug, Default, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct Config {
+    /// The directory containing plan files.
+    pub dir: Option<PathBuf>,
+    /// The path pattern of plan files, or the name of a preset.
+    pub pattern: Option<String>,
+    /// The workspace used when `--workspace` isn't given.
+    pub default_workspace: Option<String>,
+    /// Named plan directories, e.g. `work` and `personal`.
+    pub workspaces: BTreeMap<String, PathBuf>,
+    /// How many previous versions of each plan to keep in `.history`.
+    pub history_retention: Option<usize>,
+    /// Whether to cache directory listings in `.cache/index`.
+    pub index_cache: bool,
+    /// The time of day at which a new plan starts, as HH:MM.
+    pub day_starts_at: Option<String>,
+    /// An iCalendar file whose events are added to each new plan.
+    pub import_ics: Option<PathBuf>,
+}
+
+impl Config {
+    /// Gets the path of the config file.
+    pub fn path() -> Option<PathBuf> {
+        let mut path = dirs::config_dir()?;
+        path.push(CONFIG_DIR);
+        path.push(CONFIG_FILE);
+        Some(path)
+    }
+
+    /// Loads the config file, falling back to the defaults if it doesn't exist.
+    pub fn load() -> anyhow::Result<Self> {
+        let path = match Self::path() {
+            Some(p) if p.exists() => p,
+            _ => return Ok(Config::default()),
+        };
+
+        log::debug!("Loading config from {:#?}", path);
+        let text = fs::read_to_string(&path)
+            .with_context(|| format!("Could not read config file {:?}", path))?;
+        let mut config: Config =
+            toml::from_str(&text).with_context(|| format!("Invalid config file {:?}", path))?;
+
+        config.dir = config.dir.map(expand_home);
+        config.import_ics = config.import_ics.map(expand_home);
+        for dir in config.workspaces.values_mut() {
+            *dir = expand_home(dir.to_owned());
+        }
+
+        Ok(config)
+    }
+
+    /// Gets the path pattern of plan files.
+    pub fn file_pattern(&self) -> anyhow::Result<FilePattern> {
+        match &self.pattern {
+            Some(p) => FilePattern::new(p),
+            None => Ok(FilePattern::default()),
+        }
+    }
+
+    /// Gets the time of day at which a new plan starts, which defaults to
+    /// midnight.
+    pub fn day_start(&self) -> anyhow::Result<NaiveTime> {
+        match &self.day_starts_at {
+            Some(t) => NaiveTime::parse_from_str(t, "%H:%M")
+                .with_context(|| format!("Invalid day_starts_at {:?}, expected HH:MM", t)),
+            None => Ok(NaiveTime::from_hms(0, 0, 0)),
+        }
+    }
+
+    /// Gets the directory of a named workspace.
+    pub fn workspace_dir(&self, name: &str) -> anyhow::Result<&PathBuf> {
+        self.workspaces.get(name).ok_or_else(|| {
+            let names: Vec<&str> = self.workspaces.keys().map(String::as_str).collect();
+            anyhow::anyhow!(
+                "Unknown workspace `{}`.  Configured workspaces: {}",
+                name,
+                if names.is_empty() {
+                    "(none)".to_string()
+                } else {
+                    names.join(", ")
+                }
+            )
+        })
+    }
+
+    /// Renders a commented default config file.  The plan directory, if
+    /// given, is suggested but left commented out, since `--dir` and
+    /// `PLANNER_DIR` are meant for a single run.
+    pub fn default_toml(dir: Option<&PathBuf>) -> String {
+        let dir = match dir {
+            Some(d) => format!("# dir = {:?}", d.display().to_string()),
+            None => "# dir = \"~/.planner\"".to_string(),
+        };
+
+        format!(
+            "# Planner configuration.\n\
+             \n\
+             # The directory containing your plan files.  Defaults to `~/.planner`.\n\
+             {}\n\
+             \n\
+             # The path of plan files within the directory, as a strftime-style pattern.\n\
+             # Use `/` for subdirectories, or one of the presets `planner`, `obsidian`,\n\
+             # `logseq` and `nested`.\n\
+             # pattern = \"%Y.%m.%d.plan.md\"\n\
+             \n\
+             # How many previous versions of each plan to keep.  Set to 0 to disable.\n\
+             # history_retention = 10\n\
+             \n\
+             # Cache directory listings, which speeds up large, nested plan archives.\n\
+             # index_cache = false\n\
+             \n\
+             # The time at which a new day's plan starts, for working past midnight.\n\
+             # day_starts_at = \"04:00\"\n\
+             \n\
+             # A calendar file whose events are added to each new day's plan.\n\
+             # import_ics = \"~/calendars/work.ics\"\n\
+             \n\
+             # Named workspaces, selected with `planner -w NAME`.\n\
+             # default_workspace = \"work\"\n\
+             #\n\
+             # [workspaces]\n\
+             # work = \"~/plans/work\"\n\
+             # personal = \"~/plans/personal\"\n",
+            dir
+        )
+    }
+}
+
+/// Expands a leading `~` in a path to the home directory.
+fn expand_home(path: PathBuf) -> PathBuf {
+    match (path.strip_prefix("~"), dirs::home_dir()) {
+        (Ok(rest), Some(home)) => home.join(rest),
+        _ => path,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn default_toml_parses() {
+        let dir = PathBuf::from("/tmp/plans");
+        let toml = Config::default_toml(Some(&dir));
+        assert!(toml.contains("\n# dir = \"/tmp/plans\"\n"));
+        let config: Config = toml::from_str(&toml).unwrap();
+        assert_eq!(config.dir, None);
+
+        let config: Config = toml::from_str(&Config::default_toml(None)).unwrap();
+        assert_eq!(config.dir, None);
+        assert!(config.workspaces.is_empty());
+    }
+
+    #[test]
+    fn workspaces() {
+        let config: Config = toml::from_str(
+            "default_workspace = \"work\"\n\
+             [workspaces]\n\
+             work = \"/plans/work\"\n\
+             personal = \"/plans/personal\"\n",
+        )
+        .unwrap();
+
+        assert_eq!(config.default_workspace.as_deref(), Some("work"));
+        assert_eq!(
+            config.workspace_dir("personal").unwrap(),
+            &PathBuf::from("/plans/personal")
+        );
+        assert!(config.workspace_dir("play").is_err());
+    }
+
+    #[test]
+    fn day_start() {
+        let config: Config = toml::from_str("day_starts_at = \"04:30\"").unwrap();
+        assert_eq!(config.day_start().unwrap(), NaiveTime::from_hms(4, 30, 0));
+
+        assert_eq!(
+            Config::default().day_start().unwrap(),
+            NaiveTime::from_hms(0, 0, 0)
+        );
+        let config: Config = toml::from_str("day_starts_at = \"25:00\"").unwrap();
+        assert!(config.day_start().is_err());
+    }
+}
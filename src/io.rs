@@ -1,154 +1,538 @@
-use anyhow::Result;
+mod error;
+mod fingerprint;
+mod history;
+mod index;
+mod pattern;
+mod plan_cache;
+mod store;
+
 use chrono::NaiveDate;
+use rayon::prelude::*;
 use std::{
-    fs, io,
+    fs,
+    io::{self, Write},
+    ops::RangeBounds,
     path::{Path, PathBuf},
     process::Command,
 };
 
-use crate::plan::Plan;
+pub use self::error::PlanError;
+pub use self::fingerprint::Fingerprint;
+pub use self::history::History;
+pub use self::index::PlanIndex;
+pub use self::pattern::FilePattern;
+pub use self::store::{FsStore, MemoryStore, Metadata, PlanStore};
+use crate::plan::{
+    format::{self, PlanFormat},
+    Plan,
+};
+use crate::template::{self, STARTER_TEMPLATE};
 
-const LOG_EXT: &str = "plan.md";
 const EDITOR: &str = "vim.bat";
 
 #[derive(Debug)]
 pub struct PlanFile {
+    date: NaiveDate,
     path: PathBuf,
     plan: Plan,
+    fingerprint: Fingerprint,
 }
 
 impl PlanFile {
-    fn new(path: PathBuf) -> Result<Self> {
-        // TODO: validate path exists, is valid
-        let doc = fs::read_to_string(path.to_owned())?;
-        let plan = Plan::from_markdown(&doc)?;
-        Ok(PlanFile { path, plan })
+    /// Replaces the contents of the plan file.
+    ///
+    /// Fails with [PlanError::Modified] if the file was changed by another
+    /// program since it was loaded.
+    pub fn write<S: PlanStore>(
+        &mut self,
+        plan_dir: &PlanDirectory<S>,
+        contents: &str,
+    ) -> Result<(), PlanError> {
+        let plan = self
+            .format()
+            .parse(contents)
+            .map_err(|source| PlanError::Unparseable {
+                path: self.path.to_owned(),
+                source,
+            })?;
+
+        self.fingerprint = plan_dir.write_unchanged(self.date, contents, &self.fingerprint)?;
+        self.plan = plan;
+        Ok(())
+    }
+
+    /// Replaces the plan, writing it in the file's format.
+    pub fn write_plan<S: PlanStore>(
+        &mut self,
+        plan_dir: &PlanDirectory<S>,
+        plan: &Plan,
+    ) -> Result<(), PlanError> {
+        self.write(plan_dir, &self.format().serialize(plan))
     }
 
     pub fn edit(&self) {
-        log::debug!("Opening plan file for editing: {:#?}", &self.path);
+        edit(&self.path);
+    }
 
-        Command::new(EDITOR)
-            .arg(&self.path)
-            .status()
-            .expect("Editor failed to start.");
+    /// Get the format of the plan file, from its extension.
+    pub fn format(&self) -> &'static dyn PlanFormat {
+        format::for_path(&self.path)
+    }
+
+    /// Get the date the plan is stored under.
+    pub fn date(&self) -> NaiveDate {
+        self.date
+    }
+
+    /// Get the path of the plan file.
+    pub fn path(&self) -> &Path {
+        &self.path
     }
 
     /// Get a reference to the plan from the file.
     pub fn plan(&self) -> &Plan {
         &self.plan
     }
+}
 
-    pub fn create_copy(&self, path: PathBuf, date: NaiveDate) -> anyhow::Result<Self> {
-        let mut new_plan = self.plan.clone();
-        new_plan.clean();
-        new_plan.set_date(date);
+/// A collection of plans, one per date, kept in a [PlanStore].
+pub struct PlanDirectory<S = FsStore> {
+    store: S,
+}
 
-        let md = new_plan.to_markdown();
-        std::fs::write(path.to_owned(), md)?;
+impl PlanDirectory<FsStore> {
+    pub fn new(path: PathBuf, pattern: FilePattern) -> Self {
+        Self::with_store(FsStore::new(path, pattern))
+    }
 
-        Ok(Self {
-            path,
-            plan: new_plan,
-        })
+    /// Sets whether directory listings are cached in `.cache/index`.
+    pub fn with_index_cache(self, index_cache: bool) -> Self {
+        Self::with_store(self.store.with_index_cache(index_cache))
     }
-}
 
-pub struct PlanDirectory {
-    path: PathBuf,
+    /// Sets how many previous versions of each plan are kept.
+    pub fn with_history_retention(self, retention: usize) -> Self {
+        Self::with_store(self.store.with_history_retention(retention))
+    }
+
+    /// Get the path of the plan directory.
+    pub fn path(&self) -> &Path {
+        self.store.dir()
+    }
+
+    /// Gets the plan for a date, like [PlanDirectory::get_plan], but reuses
+    /// the parsed plan cached in `.cache/plan.json` if the plan hasn't changed
+    /// since.  Only the last plan read this way is cached, which keeps
+    /// frequent reads of the same day (e.g. from a shell prompt) cheap.
+    pub fn get_plan_cached(&self, date: NaiveDate) -> Result<Plan, PlanError> {
+        let cache = self.path().join(plan_cache::CACHE_FILE);
+        let path = self.store.path(date);
+
+        let doc = match self.store.read(date) {
+            Ok(doc) => doc,
+            Err(e) if e.kind() == io::ErrorKind::NotFound => return Err(PlanError::Missing(path)),
+            Err(source) => return Err(PlanError::Io { path, source }),
+        };
+        if let Some(plan) = plan_cache::read(&cache, date, &doc) {
+            log::trace!("Using cached plan for {:#?}", date);
+            return Ok(plan);
+        }
+
+        let plan = format::for_path(&path)
+            .parse(&doc)
+            .map_err(|source| PlanError::Unparseable { path, source })?;
+        if let Err(e) = plan_cache::write(&cache, date, &doc, &plan) {
+            log::warn!("Could not save the plan cache: {}", e);
+        }
+        Ok(plan)
+    }
 }
 
-impl PlanDirectory {
-    pub fn new(path: PathBuf) -> Self {
-        log::debug!("Initializing plan directory at '{:#?}'.", path);
-        PlanDirectory { path }
+impl<S: PlanStore> PlanDirectory<S> {
+    pub fn with_store(store: S) -> Self {
+        PlanDirectory { store }
     }
 
+    /// Get a reference to the underlying store.
+    pub fn store(&self) -> &S {
+        &self.store
+    }
+
+    /// Creates a plan from the directory's template, or the starter template
+    /// if the directory doesn't have one.
     pub fn create_plan(&self, date: NaiveDate) -> anyhow::Result<PlanFile> {
-        let plan_path = self.get_plan_path(date);
+        let template = self
+            .store
+            .read_template()?
+            .unwrap_or_else(|| STARTER_TEMPLATE.to_string());
+
+        // Templates are markdown, so plans in other formats are converted.
+        let mut contents = template::render(&template, date);
+        let format = format::for_path(&self.store.path(date));
+        if format.name() != format::Markdown.name() {
+            contents = format.serialize(&Plan::from_markdown(&contents)?);
+        }
 
         log::debug!("Creating plan file for date: {:#?}", date);
-        std::fs::File::create(plan_path.to_owned())?;
+        self.store.create(date, &contents)?;
 
-        PlanFile::new(plan_path)
+        Ok(self.get_plan(date)?)
     }
 
-    /// Creates a clean copy of the provided plan file, and sets the date.
+    /// Creates the plan for a date by rolling over the provided plan file (see
+    /// [Plan::rollover]).
     pub fn copy_plan(&self, original_plan: PlanFile, date: NaiveDate) -> anyhow::Result<PlanFile> {
-        let path = self.get_plan_path(date);
-
         log::debug!(
             "Creating a copy of plan file `{:#?}` for date `{:#?}`",
             original_plan.path,
             date
         );
-        original_plan.create_copy(path, date)
+
+        let plan = original_plan.plan.rollover(date);
+        let format = format::for_path(&self.store.path(date));
+        self.store.create(date, &format.serialize(&plan))?;
+
+        Ok(self.get_plan(date)?)
     }
 
-    pub fn get_plan(&self, date: NaiveDate) -> Option<PlanFile> {
-        let plan_path = self.get_plan_path(date);
+    /// Gets the plan for a date, creating it from the most recent earlier plan
+    /// (or the template, if there isn't one) if it doesn't exist yet.
+    pub fn get_or_create_plan(&self, date: NaiveDate) -> anyhow::Result<PlanFile> {
+        match self.get_plan(date) {
+            Ok(p) => Ok(p),
+            Err(PlanError::Missing(_)) => match self.get_most_recent_plan(date)? {
+                Some(p) => self.copy_plan(p, date),
+                None => self.create_plan(date),
+            },
+            Err(e) => Err(e.into()),
+        }
+    }
 
-        if plan_path.exists() {
-            log::trace!("Plan file found at path: {:#?}", plan_path);
-            match PlanFile::new(plan_path) {
-                Ok(file) => Some(file),
-                _ => None,
-            }
-        } else {
-            log::trace!("No plan file found at path: {:#?}", plan_path);
-            None
+    /// Moves the plan for one date to another date, without changing its
+    /// contents.  Fails if there already is a plan for the new date.
+    pub fn move_plan(&self, from: NaiveDate, to: NaiveDate) -> io::Result<()> {
+        let contents = self.store.read(from)?;
+        self.store.create(to, &contents)?;
+        self.store.delete(from)
+    }
+
+    /// Gets the plan for a date.
+    ///
+    /// Returns [PlanError::Missing] if there's no plan for the date, and
+    /// [PlanError::Unparseable] if there is one, but it's broken.
+    pub fn get_plan(&self, date: NaiveDate) -> Result<PlanFile, PlanError> {
+        let path = self.store.path(date);
+        log::trace!("Loading plan file at path: {:#?}", path);
+
+        let loaded = self.store.read(date).and_then(|doc| {
+            let metadata = self.store.metadata(date)?;
+            Ok((doc, metadata))
+        });
+        let (doc, metadata) = match loaded {
+            Ok(loaded) => loaded,
+            Err(e) if e.kind() == io::ErrorKind::NotFound => return Err(PlanError::Missing(path)),
+            Err(source) => return Err(PlanError::Io { path, source }),
+        };
+
+        let fingerprint = Fingerprint::new(&doc, metadata.modified);
+        match format::for_path(&path).parse(&doc) {
+            Ok(plan) => Ok(PlanFile {
+                date,
+                path,
+                plan,
+                fingerprint,
+            }),
+            Err(source) => Err(PlanError::Unparseable { path, source }),
         }
     }
 
-    pub fn get_most_recent_plan(&self) -> Option<PlanFile> {
-        let today = chrono::Local::today().naive_local();
-        let plan_paths = self.get_files();
+    /// Gets the most recent plan dated on or before `date`.
+    ///
+    /// A broken plan is returned as an error, rather than skipped in favour of
+    /// an older one.
+    pub fn get_most_recent_plan(&self, date: NaiveDate) -> Result<Option<PlanFile>, PlanError> {
+        let index = self.index().map_err(|source| PlanError::Io {
+            path: self.store.path(date),
+            source,
+        })?;
 
-        let file = match plan_paths {
-            Ok(mut paths) => {
-                paths.sort();
-                paths.reverse();
-                paths
-                    .into_iter()
-                    .map(PlanFile::new)
-                    .flatten()
-                    .find(|p| p.plan().date() <= today)
-            }
-            _ => None,
+        let file = match index.latest(date) {
+            Some(latest) => Some(self.get_plan(latest)?),
+            None => None,
         };
 
         log::debug!("Most recent plan: {:#?}", file);
-        file
+        Ok(file)
     }
 
-    fn get_plan_path(&self, date: NaiveDate) -> PathBuf {
-        let mut plan_path = self.path.to_owned();
-        let file_format = format!("%Y.%m.%d.{}", LOG_EXT);
-        let today_file_name = date.format(file_format.as_str());
+    /// Loads the plans in a date range in parallel, oldest first.
+    pub fn load_range<R>(
+        &self,
+        range: R,
+    ) -> io::Result<Vec<(NaiveDate, Result<PlanFile, PlanError>)>>
+    where
+        R: RangeBounds<NaiveDate>,
+    {
+        let dates: Vec<NaiveDate> = self.index()?.range(range).collect();
 
-        plan_path.push(&today_file_name.to_string());
-        plan_path
+        Ok(dates
+            .into_par_iter()
+            .map(|date| (date, self.get_plan(date)))
+            .collect())
     }
 
-    fn get_files(&self) -> io::Result<Vec<PathBuf>> {
-        Ok(fs::read_dir(&self.path)?
-            .map(|res| res.map(|entry| entry.path()))
-            .filter(|e| e.is_ok())
-            .map(|e| e.unwrap())
-            .filter(|e| Self::is_plan_file(e))
-            .collect::<Vec<PathBuf>>())
+    /// Builds an index of the dates that have a plan.
+    pub fn index(&self) -> io::Result<PlanIndex> {
+        Ok(PlanIndex::new(self.store.list_dates()?))
     }
 
-    fn is_plan_file(path: &Path) -> bool {
-        let is_plan = match path.file_name() {
-            Some(file_name) => match file_name.to_str() {
-                Some(ext_str) => ext_str.ends_with(LOG_EXT),
-                _ => false,
-            },
-            _ => false,
+    pub fn get_plan_path(&self, date: NaiveDate) -> PathBuf {
+        self.store.path(date)
+    }
+
+    /// Overwrites the plan for a date, but only if it still matches
+    /// `fingerprint`.  Returns the fingerprint of the new contents.
+    fn write_unchanged(
+        &self,
+        date: NaiveDate,
+        contents: &str,
+        fingerprint: &Fingerprint,
+    ) -> Result<Fingerprint, PlanError> {
+        let path = self.store.path(date);
+        let io_error = |source| PlanError::Io {
+            path: path.to_owned(),
+            source,
+        };
+
+        let current = match self.store.read(date) {
+            Ok(c) => Fingerprint::new(&c, self.store.metadata(date).map_err(io_error)?.modified),
+            Err(e) if e.kind() == io::ErrorKind::NotFound => {
+                return Err(PlanError::Modified(path.to_owned()))
+            }
+            Err(e) => return Err(io_error(e)),
         };
+        if current != *fingerprint {
+            return Err(PlanError::Modified(path.to_owned()));
+        }
+
+        self.store.write(date, contents).map_err(io_error)?;
+        let metadata = self.store.metadata(date).map_err(io_error)?;
+        Ok(Fingerprint::new(contents, metadata.modified))
+    }
+}
+
+/// Opens a file in the editor.
+pub fn edit(path: &Path) {
+    log::debug!("Opening plan file for editing: {:#?}", path);
+
+    Command::new(EDITOR)
+        .arg(path)
+        .status()
+        .expect("Editor failed to start.");
+}
+
+/// Writes a file by writing a temporary file next to it, then renaming it over
+/// the original, so that the file is never left half-written.
+fn write_atomic(path: &Path, contents: &str) -> io::Result<()> {
+    let file_name = path.file_name().unwrap_or_default().to_string_lossy();
+    let temp_path = path.with_file_name(format!(".{}.tmp", file_name));
+
+    let mut file = fs::File::create(&temp_path)?;
+    file.write_all(contents.as_bytes())?;
+    file.sync_all()?;
+
+    fs::rename(&temp_path, path)
+}
+
+/// Writes a new file, failing with `AlreadyExists` if there is a file at the
+/// path.  The contents are written to a temporary file, which is then hard
+/// linked into place, so an existing file is never overwritten, even if it's
+/// created while the new one is being written.
+fn write_new(path: &Path, contents: &str) -> io::Result<()> {
+    let file_name = path.file_name().unwrap_or_default().to_string_lossy();
+    let temp_path = path.with_file_name(format!(".{}.{}.new", file_name, std::process::id()));
+
+    let mut file = fs::File::create(&temp_path)?;
+    file.write_all(contents.as_bytes())?;
+    file.sync_all()?;
+
+    let linked = fs::hard_link(&temp_path, path);
+    fs::remove_file(&temp_path)?;
+    linked.map_err(|e| match e.kind() {
+        io::ErrorKind::AlreadyExists => {
+            io::Error::new(e.kind(), format!("{:?} already exists", path))
+        }
+        _ => e,
+    })
+}
+
+fn create_parent_dir(path: &Path) -> io::Result<()> {
+    match path.parent() {
+        Some(parent) => fs::create_dir_all(parent),
+        None => Ok(()),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::plan::tasks::{Task, TaskList, TaskStatus};
+
+    #[test]
+    fn get_plan_distinguishes_missing_and_unparseable() {
+        let dir = tempfile::tempdir().unwrap();
+        let plan_dir = PlanDirectory::new(dir.path().to_owned(), FilePattern::default());
+        let date = NaiveDate::from_ymd(2021, 6, 1);
+
+        assert!(matches!(
+            plan_dir.get_plan(date),
+            Err(PlanError::Missing(_))
+        ));
+
+        fs::write(plan_dir.get_plan_path(date), "# 06/31/2021\n").unwrap();
+        assert!(matches!(
+            plan_dir.get_plan(date),
+            Err(PlanError::Unparseable { .. })
+        ));
+        assert!(matches!(
+            plan_dir.get_most_recent_plan(date),
+            Err(PlanError::Unparseable { .. })
+        ));
+
+        // A broken plan must never be replaced by a new one.
+        assert!(plan_dir.create_plan(date).is_err());
+        assert_eq!(
+            fs::read_to_string(plan_dir.get_plan_path(date)).unwrap(),
+            "# 06/31/2021\n"
+        );
+    }
+
+    #[test]
+    fn write_refuses_modified_plan() {
+        let dir = tempfile::tempdir().unwrap();
+        let plan_dir = PlanDirectory::new(dir.path().to_owned(), FilePattern::default());
+        let date = NaiveDate::from_ymd(2021, 6, 1);
+        let mut file = plan_dir.create_plan(date).unwrap();
+
+        let edited = file
+            .plan()
+            .to_markdown()
+            .replace("Take a walk", "Go for a run");
+        file.write(&plan_dir, &edited).unwrap();
+
+        // Another program changes the file after it was loaded.
+        fs::write(file.path(), "# 06/01/2021\nTuesday\n").unwrap();
+        assert!(matches!(
+            file.write(&plan_dir, &edited),
+            Err(PlanError::Modified(_))
+        ));
+        assert_eq!(
+            fs::read_to_string(file.path()).unwrap(),
+            "# 06/01/2021\nTuesday\n"
+        );
+    }
+
+    #[test]
+    fn rolls_over_in_memory() {
+        let plan_dir = PlanDirectory::with_store(MemoryStore::new());
+        let monday = NaiveDate::from_ymd(2021, 5, 31);
+        let wednesday = NaiveDate::from_ymd(2021, 6, 2);
+
+        let mut file = plan_dir.get_or_create_plan(monday).unwrap();
+        assert_eq!(file.plan().date(), monday);
+        let edited = file
+            .plan()
+            .to_markdown()
+            .replace("Take a walk", "Go for a run");
+        file.write(&plan_dir, &edited).unwrap();
+
+        let file = plan_dir.get_or_create_plan(wednesday).unwrap();
+        assert_eq!(file.plan().date(), wednesday);
+        assert!(file.plan().to_markdown().contains("Go for a run"));
+        assert_eq!(
+            plan_dir.index().unwrap().range(..).collect::<Vec<_>>(),
+            vec![monday, wednesday]
+        );
+    }
+
+    #[test]
+    fn create_plan_uses_store_template() {
+        let template = "# {{date}}\n{{day}}\n\n## Notes\n";
+        let plan_dir = PlanDirectory::with_store(MemoryStore::new().with_template(template));
+        let date = NaiveDate::from_ymd(2021, 6, 1);
+
+        plan_dir.create_plan(date).unwrap();
+        assert_eq!(
+            plan_dir.store().read(date).unwrap(),
+            "# 06/01/2021\nTuesday\n\n## Notes\n"
+        );
+        assert!(plan_dir.create_plan(date).is_err());
+    }
+
+    #[test]
+    fn org_plans() {
+        let dir = tempfile::tempdir().unwrap();
+        let pattern = FilePattern::new("org").unwrap();
+        let plan_dir = PlanDirectory::new(dir.path().to_owned(), pattern);
+        let monday = NaiveDate::from_ymd(2021, 6, 7);
+        let tuesday = NaiveDate::from_ymd(2021, 6, 8);
+
+        let mut file = plan_dir.create_plan(monday).unwrap();
+        assert_eq!(file.format().name(), "org");
+        assert!(plan_dir
+            .store()
+            .read(monday)
+            .unwrap()
+            .starts_with("* 06/07/2021\nMonday\n"));
+
+        let mut plan = file.plan().clone();
+        plan.tasks_mut()
+            .get_or_insert_with(|| TaskList::new(Vec::new()))
+            .category_mut("Work")
+            .tasks_mut()
+            .push(Task::new("Write report", TaskStatus::Incomplete));
+        file.write_plan(&plan_dir, &plan).unwrap();
+
+        let file = plan_dir.get_or_create_plan(tuesday).unwrap();
+        assert!(file.path().ends_with("2021.06.08.plan.org"));
+        assert!(plan_dir
+            .store()
+            .read(tuesday)
+            .unwrap()
+            .contains("**** TODO Write report\n"));
+    }
+
+    #[test]
+    fn caches_parsed_plan() {
+        let dir = tempfile::tempdir().unwrap();
+        let plan_dir = PlanDirectory::new(dir.path().to_owned(), FilePattern::default());
+        let date = NaiveDate::from_ymd(2021, 6, 1);
+
+        assert!(matches!(
+            plan_dir.get_plan_cached(date),
+            Err(PlanError::Missing(_))
+        ));
+
+        let mut file = plan_dir.create_plan(date).unwrap();
+        let plan = plan_dir.get_plan_cached(date).unwrap();
+        assert!(dir.path().join(plan_cache::CACHE_FILE).exists());
+        assert_eq!(plan.to_markdown(), file.plan().to_markdown());
+
+        let edited = plan.to_markdown().replace("Take a walk", "Go for a run");
+        file.write(&plan_dir, &edited).unwrap();
+        let plan = plan_dir.get_plan_cached(date).unwrap();
+        assert!(plan.to_markdown().contains("Go for a run"));
+    }
+
+    #[test]
+    fn write_new_never_overwrites() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("2021-06-01.md");
 
-        log::debug!("{:#?} is plan file: {}", path, is_plan);
-        is_plan
+        write_new(&path, "first").unwrap();
+        let err = write_new(&path, "second").unwrap_err();
+        assert_eq!(err.kind(), io::ErrorKind::AlreadyExists);
+        assert_eq!(fs::read_to_string(&path).unwrap(), "first");
+        assert_eq!(fs::read_dir(dir.path()).unwrap().count(), 1);
     }
 }
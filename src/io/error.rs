@@ -0,0 +1,44 @@
+use std::{error::Error, fmt, io, path::PathBuf};
+
+/// An error loading or saving a plan file.
+#[derive(Debug)]
+pub enum PlanError {
+    /// The plan file doesn't exist.
+    Missing(PathBuf),
+    /// The plan file exists, but couldn't be parsed.
+    Unparseable {
+        path: PathBuf,
+        source: anyhow::Error,
+    },
+    /// The plan file couldn't be read or written.
+    Io { path: PathBuf, source: io::Error },
+    /// The plan file was changed by another program after it was loaded.
+    Modified(PathBuf),
+}
+
+impl fmt::Display for PlanError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            PlanError::Missing(path) => write!(f, "Plan file {:?} does not exist", path),
+            PlanError::Unparseable { path, .. } => {
+                write!(f, "Could not parse plan file {:?}", path)
+            }
+            PlanError::Io { path, .. } => write!(f, "Could not access plan file {:?}", path),
+            PlanError::Modified(path) => write!(
+                f,
+                "Plan file {:?} was changed by another program since it was loaded; not saving",
+                path
+            ),
+        }
+    }
+}
+
+impl Error for PlanError {
+    fn source(&self) -> Option<&(dyn Error + 'static)> {
+        match self {
+            PlanError::Missing(_) | PlanError::Modified(_) => None,
+            PlanError::Unparseable { source, .. } => Some(source.as_ref()),
+            PlanError::Io { source, .. } => Some(source),
+        }
+    }
+}
@@ -0,0 +1,42 @@
+use std::{
+    collections::hash_map::DefaultHasher,
+    hash::{Hash, Hasher},
+    time::SystemTime,
+};
+
+/// Identifies the contents of a plan when it was loaded, so that changes made
+/// by other programs (an open editor, a sync client) can be detected before the
+/// plan is overwritten.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Fingerprint {
+    modified: Option<SystemTime>,
+    hash: u64,
+}
+
+impl Fingerprint {
+    /// Creates a fingerprint from a plan's contents and modification time.
+    pub fn new(contents: &str, modified: Option<SystemTime>) -> Self {
+        let mut hasher = DefaultHasher::new();
+        contents.hash(&mut hasher);
+
+        Fingerprint {
+            modified,
+            hash: hasher.finish(),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn compares_time_and_contents() {
+        let now = Some(SystemTime::now());
+        let fingerprint = Fingerprint::new("# 06/01/2021\n", now);
+
+        assert_eq!(fingerprint, Fingerprint::new("# 06/01/2021\n", now));
+        assert_ne!(fingerprint, Fingerprint::new("# 06/02/2021\n", now));
+        assert_ne!(fingerprint, Fingerprint::new("# 06/01/2021\n", None));
+    }
+}
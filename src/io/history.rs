@@ -0,0 +1,180 @@
+use std::{
+    fs, io,
+    path::{Path, PathBuf},
+};
+
+use chrono::NaiveDateTime;
+
+use super::write_atomic;
+
+/// The name of the history folder in the plan directory.
+pub const HISTORY_DIR: &str = ".history";
+
+const TIMESTAMP_FORMAT: &str = "%Y%m%dT%H%M%S%.f";
+const VERSION_EXT: &str = "bak";
+/// The name of the file holding the version replaced by the last undo.
+const REDO_FILE: &str = "redo.bak";
+
+/// A saved version of a plan file.
+#[derive(Debug)]
+pub struct Version {
+    timestamp: NaiveDateTime,
+    path: PathBuf,
+}
+
+impl Version {
+    /// Get the time the version was replaced.
+    pub fn timestamp(&self) -> NaiveDateTime {
+        self.timestamp
+    }
+
+    /// Reads the contents of the version.
+    pub fn read(&self) -> io::Result<String> {
+        fs::read_to_string(&self.path)
+    }
+}
+
+/// Stores previous versions of plan files in `.history`, keeping the most
+/// recent `retention` versions of each plan.
+#[derive(Debug)]
+pub struct History {
+    path: PathBuf,
+    retention: usize,
+}
+
+impl History {
+    pub fn new(plan_dir: &Path, retention: usize) -> Self {
+        History {
+            path: plan_dir.join(HISTORY_DIR),
+            retention,
+        }
+    }
+
+    /// Saves a version of a plan file.  `relative_path` is the path of the plan
+    /// file relative to the plan directory.  Any version kept for a redo is
+    /// dropped, since the plan has moved on from it.
+    pub fn save(&self, relative_path: &Path, contents: &str) -> io::Result<()> {
+        if self.retention == 0 {
+            return Ok(());
+        }
+
+        let dir = self.path.join(relative_path);
+        fs::create_dir_all(&dir)?;
+        remove_if_exists(&dir.join(REDO_FILE))?;
+
+        let timestamp = chrono::Local::now().naive_local();
+        let file_name = format!("{}.{}", timestamp.format(TIMESTAMP_FORMAT), VERSION_EXT);
+        log::debug!("Saving version {:#?} of {:#?}", file_name, relative_path);
+        write_atomic(&dir.join(file_name), contents)?;
+
+        let versions = self.versions(relative_path)?;
+        let expired = versions.len().saturating_sub(self.retention);
+        for version in &versions[..expired] {
+            log::trace!("Removing expired version {:#?}", version.path);
+            fs::remove_file(&version.path)?;
+        }
+
+        Ok(())
+    }
+
+    /// Lists the saved versions of a plan file, oldest first.
+    pub fn versions(&self, relative_path: &Path) -> io::Result<Vec<Version>> {
+        let dir = self.path.join(relative_path);
+        if !dir.is_dir() {
+            return Ok(Vec::new());
+        }
+
+        let mut versions: Vec<Version> = fs::read_dir(&dir)?
+            .flatten()
+            .map(|entry| entry.path())
+            .filter_map(|path| {
+                let stem = path.file_stem()?.to_str()?;
+                let timestamp = NaiveDateTime::parse_from_str(stem, TIMESTAMP_FORMAT).ok()?;
+                Some(Version { timestamp, path })
+            })
+            .collect();
+        versions.sort_by_key(|v| v.timestamp);
+
+        Ok(versions)
+    }
+
+    /// Removes and returns the most recent version of a plan file.
+    pub fn pop(&self, relative_path: &Path) -> io::Result<Option<(Version, String)>> {
+        match self.versions(relative_path)?.pop() {
+            Some(version) => {
+                let contents = version.read()?;
+                fs::remove_file(&version.path)?;
+                Ok(Some((version, contents)))
+            }
+            None => Ok(None),
+        }
+    }
+
+    /// Removes a saved version.
+    pub fn remove(&self, version: &Version) -> io::Result<()> {
+        fs::remove_file(&version.path)
+    }
+
+    /// Keeps the version of a plan file that an undo replaced, apart from the
+    /// versions that undo steps through.
+    pub fn save_redo(&self, relative_path: &Path, contents: &str) -> io::Result<()> {
+        let dir = self.path.join(relative_path);
+        fs::create_dir_all(&dir)?;
+        write_atomic(&dir.join(REDO_FILE), contents)
+    }
+
+    /// Reads the version of a plan file that the last undo replaced, if the
+    /// plan hasn't been saved since.
+    pub fn redo(&self, relative_path: &Path) -> io::Result<Option<String>> {
+        match fs::read_to_string(self.path.join(relative_path).join(REDO_FILE)) {
+            Ok(contents) => Ok(Some(contents)),
+            Err(e) if e.kind() == io::ErrorKind::NotFound => Ok(None),
+            Err(e) => Err(e),
+        }
+    }
+}
+
+fn remove_if_exists(path: &Path) -> io::Result<()> {
+    match fs::remove_file(path) {
+        Err(e) if e.kind() != io::ErrorKind::NotFound => Err(e),
+        _ => Ok(()),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn keeps_recent_versions() {
+        let dir = tempfile::tempdir().unwrap();
+        let history = History::new(dir.path(), 2);
+        let plan = Path::new("2021.06.01.plan.md");
+
+        for contents in &["one", "two", "three"] {
+            history.save(plan, contents).unwrap();
+        }
+
+        let versions = history.versions(plan).unwrap();
+        assert_eq!(versions.len(), 2);
+        assert_eq!(versions[0].read().unwrap(), "two");
+
+        let (_, contents) = history.pop(plan).unwrap().unwrap();
+        assert_eq!(contents, "three");
+        assert_eq!(history.versions(plan).unwrap().len(), 1);
+    }
+
+    #[test]
+    fn drops_redo_on_save() {
+        let dir = tempfile::tempdir().unwrap();
+        let history = History::new(dir.path(), 2);
+        let plan = Path::new("2021.06.01.plan.md");
+
+        history.save_redo(plan, "undone").unwrap();
+        assert_eq!(history.redo(plan).unwrap().as_deref(), Some("undone"));
+        assert!(history.versions(plan).unwrap().is_empty());
+
+        history.save(plan, "one").unwrap();
+        assert_eq!(history.redo(plan).unwrap(), None);
+    }
+}
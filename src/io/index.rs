@@ -0,0 +1,225 @@
+use std::{
+    collections::{BTreeSet, HashMap},
+    fmt::Write as _,
+    fs, io,
+    ops::RangeBounds,
+    path::{Path, PathBuf},
+    time::UNIX_EPOCH,
+};
+
+use chrono::NaiveDate;
+
+use super::write_atomic;
+
+/// The path of the listing cache, relative to the plan directory.
+const CACHE_FILE: &str = ".cache/index";
+const CACHE_HEADER: &str = "planner-index 1";
+
+/// The dates that have a plan, used to find plans without reading them.
+#[derive(Debug, Default)]
+pub struct PlanIndex {
+    dates: BTreeSet<NaiveDate>,
+}
+
+impl PlanIndex {
+    pub fn new(dates: Vec<NaiveDate>) -> Self {
+        PlanIndex {
+            dates: dates.into_iter().collect(),
+        }
+    }
+
+    /// Gets the most recent date with a plan, on or before `date`.
+    pub fn latest(&self, date: NaiveDate) -> Option<NaiveDate> {
+        self.dates.range(..=date).next_back().copied()
+    }
+
+    /// Iterates over the dates with a plan in a range, oldest first.
+    pub fn range<R>(&self, range: R) -> impl DoubleEndedIterator<Item = NaiveDate> + '_
+    where
+        R: RangeBounds<NaiveDate>,
+    {
+        self.dates.range(range).copied()
+    }
+}
+
+/// The entries of a directory, as of its last modification.
+#[derive(Debug, Clone, Default)]
+pub struct DirListing {
+    modified: u128,
+    pub files: Vec<String>,
+    pub dirs: Vec<String>,
+}
+
+/// Lists directories, optionally reusing listings cached on disk for
+/// directories that haven't been modified since.
+#[derive(Debug)]
+pub struct Lister {
+    root: PathBuf,
+    cache: Option<HashMap<PathBuf, DirListing>>,
+    dirty: bool,
+}
+
+impl Lister {
+    pub fn new(root: &Path, use_cache: bool) -> Self {
+        let cache = if use_cache {
+            Some(read_cache(&root.join(CACHE_FILE)).unwrap_or_default())
+        } else {
+            None
+        };
+
+        Lister {
+            root: root.to_owned(),
+            cache,
+            dirty: false,
+        }
+    }
+
+    /// Lists the non-hidden files and folders in `dir`.
+    pub fn list(&mut self, dir: &Path) -> io::Result<DirListing> {
+        let modified = fs::metadata(dir)?
+            .modified()?
+            .duration_since(UNIX_EPOCH)
+            .map_or(0, |d| d.as_nanos());
+        let relative_dir = dir.strip_prefix(&self.root).unwrap_or(dir).to_owned();
+
+        if let Some(cached) = self.cache.as_ref().and_then(|c| c.get(&relative_dir)) {
+            if cached.modified == modified {
+                log::trace!("Using cached listing of {:#?}", dir);
+                return Ok(cached.clone());
+            }
+        }
+
+        let mut listing = DirListing {
+            modified,
+            ..DirListing::default()
+        };
+        for entry in fs::read_dir(dir)?.flatten() {
+            let name = entry.file_name().to_string_lossy().to_string();
+
+            // Skip hidden files and folders, like `.git` and `.obsidian`.
+            if name.starts_with('.') {
+                continue;
+            }
+
+            if entry.path().is_dir() {
+                listing.dirs.push(name);
+            } else {
+                listing.files.push(name);
+            }
+        }
+
+        if let Some(cache) = &mut self.cache {
+            cache.insert(relative_dir, listing.clone());
+            self.dirty = true;
+        }
+
+        Ok(listing)
+    }
+
+    /// Writes the cache back to disk if it changed.
+    pub fn save(&self) -> io::Result<()> {
+        match &self.cache {
+            Some(cache) if self.dirty => {
+                let path = self.root.join(CACHE_FILE);
+                if let Some(parent) = path.parent() {
+                    fs::create_dir_all(parent)?;
+                }
+                write_atomic(&path, &write_cache(cache))
+            }
+            _ => Ok(()),
+        }
+    }
+}
+
+/// Reads the listing cache.  Each directory is a `D` line with its
+/// modification time and relative path, followed by `F` (file) and `S`
+/// (subdirectory) lines for its entries.
+fn read_cache(path: &Path) -> Option<HashMap<PathBuf, DirListing>> {
+    let text = fs::read_to_string(path).ok()?;
+    let mut lines = text.lines();
+    if lines.next()? != CACHE_HEADER {
+        return None;
+    }
+
+    let mut cache = HashMap::new();
+    let mut current: Option<(PathBuf, DirListing)> = None;
+
+    for line in lines {
+        let mut parts = line.splitn(3, '\t');
+        match (parts.next()?, parts.next()?) {
+            ("D", modified) => {
+                if let Some((dir, listing)) = current.take() {
+                    cache.insert(dir, listing);
+                }
+                let listing = DirListing {
+                    modified: modified.parse().ok()?,
+                    ..DirListing::default()
+                };
+                current = Some((PathBuf::from(parts.next()?), listing));
+            }
+            ("F", name) => current.as_mut()?.1.files.push(name.to_string()),
+            ("S", name) => current.as_mut()?.1.dirs.push(name.to_string()),
+            _ => return None,
+        }
+    }
+
+    if let Some((dir, listing)) = current {
+        cache.insert(dir, listing);
+    }
+    Some(cache)
+}
+
+fn write_cache(cache: &HashMap<PathBuf, DirListing>) -> String {
+    let mut text = String::new();
+    writeln!(text, "{}", CACHE_HEADER).unwrap();
+
+    for (dir, listing) in cache {
+        writeln!(text, "D\t{}\t{}", listing.modified, dir.display()).unwrap();
+        for file in &listing.files {
+            writeln!(text, "F\t{}", file).unwrap();
+        }
+        for subdir in &listing.dirs {
+            writeln!(text, "S\t{}", subdir).unwrap();
+        }
+    }
+
+    text
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn latest_and_range() {
+        let date = |d| NaiveDate::from_ymd(2021, 6, d);
+        let index = PlanIndex::new(vec![date(7), date(1), date(3)]);
+
+        assert_eq!(index.latest(date(5)), Some(date(3)));
+        assert_eq!(index.latest(date(7)), Some(date(7)));
+        assert_eq!(index.latest(NaiveDate::from_ymd(2021, 5, 31)), None);
+        assert_eq!(
+            index.range(date(2)..=date(7)).collect::<Vec<_>>(),
+            vec![date(3), date(7)]
+        );
+    }
+
+    #[test]
+    fn cache_round_trip() {
+        let dir = tempfile::tempdir().unwrap();
+        fs::create_dir(dir.path().join("2021")).unwrap();
+        fs::write(dir.path().join("2021.06.01.plan.md"), "").unwrap();
+
+        let mut lister = Lister::new(dir.path(), true);
+        let listing = lister.list(dir.path()).unwrap();
+        assert_eq!(listing.files, vec!["2021.06.01.plan.md"]);
+        assert_eq!(listing.dirs, vec!["2021"]);
+        lister.save().unwrap();
+
+        let cache = read_cache(&dir.path().join(CACHE_FILE)).unwrap();
+        let cached = cache.get(Path::new("")).unwrap();
+        assert_eq!(cached.files, listing.files);
+        assert_eq!(cached.dirs, listing.dirs);
+        assert_eq!(cached.modified, listing.modified);
+    }
+}
@@ -0,0 +1,122 @@
+use std::path::{Component, Path, PathBuf};
+
+use chrono::NaiveDate;
+
+/// The default pattern, e.g. `2021.06.01.plan.md`.
+pub const DEFAULT_PATTERN: &str = "%Y.%m.%d.plan.md";
+
+/// Named patterns for other tools' daily note layouts.
+const PRESETS: &[(&str, &str)] = &[
+    ("planner", DEFAULT_PATTERN),
+    ("obsidian", "%Y-%m-%d.md"),
+    ("logseq", "journals/%Y_%m_%d.md"),
+    ("nested", "%Y/%m/%d.md"),
+    ("org", "%Y.%m.%d.plan.org"),
+];
+
+/// A strftime-style pattern for plan file paths, relative to the plan
+/// directory.  Subdirectories are separated by `/`.
+#[derive(Debug, Clone, PartialEq)]
+pub struct FilePattern {
+    pattern: String,
+}
+
+impl FilePattern {
+    /// Creates a pattern from a strftime-style string, or the name of a preset
+    /// (`planner`, `obsidian`, `logseq`, `nested` or `org`).
+    pub fn new(pattern: &str) -> anyhow::Result<Self> {
+        let pattern = PRESETS
+            .iter()
+            .find(|(name, _)| *name == pattern)
+            .map_or(pattern, |(_, p)| p);
+
+        let file_pattern = FilePattern {
+            pattern: pattern.to_string(),
+        };
+
+        // A pattern is only usable if dates survive a round trip through it.
+        let sample = NaiveDate::from_ymd(2021, 12, 31);
+        if file_pattern.parse(&file_pattern.path_for(sample)) != Some(sample) {
+            anyhow::bail!(
+                "File pattern `{}` must contain the year, month and day (e.g. `%Y-%m-%d.md`).",
+                pattern
+            );
+        }
+
+        Ok(file_pattern)
+    }
+
+    /// Gets the pattern string.
+    pub fn as_str(&self) -> &str {
+        &self.pattern
+    }
+
+    /// Gets the number of directories a plan file is nested in.
+    pub fn depth(&self) -> usize {
+        self.pattern.matches('/').count()
+    }
+
+    /// Gets the path of the plan for a date, relative to the plan directory.
+    pub fn path_for(&self, date: NaiveDate) -> PathBuf {
+        date.format(&self.pattern)
+            .to_string()
+            .split('/')
+            .collect::<PathBuf>()
+    }
+
+    /// Parses the date from a path relative to the plan directory.
+    pub fn parse(&self, relative_path: &Path) -> Option<NaiveDate> {
+        let mut parts = Vec::new();
+        for component in relative_path.components() {
+            match component {
+                Component::Normal(part) => parts.push(part.to_str()?),
+                _ => return None,
+            }
+        }
+
+        NaiveDate::parse_from_str(&parts.join("/"), &self.pattern).ok()
+    }
+}
+
+impl Default for FilePattern {
+    fn default() -> Self {
+        FilePattern {
+            pattern: DEFAULT_PATTERN.to_string(),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn default_pattern() {
+        let pattern = FilePattern::default();
+        let date = NaiveDate::from_ymd(2021, 6, 1);
+
+        assert_eq!(pattern.path_for(date), PathBuf::from("2021.06.01.plan.md"));
+        assert_eq!(pattern.parse(Path::new("2021.06.01.plan.md")), Some(date));
+        assert_eq!(pattern.parse(Path::new("template.md")), None);
+    }
+
+    #[test]
+    fn nested_pattern() {
+        let pattern = FilePattern::new("nested").unwrap();
+        let date = NaiveDate::from_ymd(2021, 6, 1);
+
+        assert_eq!(pattern.depth(), 2);
+        assert_eq!(
+            pattern.path_for(date),
+            ["2021", "06", "01.md"].iter().collect::<PathBuf>()
+        );
+        assert_eq!(pattern.parse(&pattern.path_for(date)), Some(date));
+        assert_eq!(pattern.parse(Path::new("2021/06.md")), None);
+    }
+
+    #[test]
+    fn invalid_pattern() {
+        assert!(FilePattern::new("%Y-%m.md").is_err());
+        assert!(FilePattern::new("plan.md").is_err());
+    }
+}
@@ -0,0 +1,69 @@
+use std::{fs, io, path::Path};
+
+use chrono::NaiveDate;
+use serde::{Deserialize, Serialize};
+
+use super::{create_parent_dir, write_atomic};
+use crate::plan::Plan;
+
+/// The path of the parsed plan cache, relative to the plan directory.
+pub const CACHE_FILE: &str = ".cache/plan.json";
+
+/// A parsed plan, and a hash of the document it was parsed from.  Only the
+/// most recently read plan is cached.
+#[derive(Debug, Serialize, Deserialize)]
+struct CachedPlan {
+    date: NaiveDate,
+    hash: u64,
+    plan: Plan,
+}
+
+/// Reads the cached plan for a date, if it was parsed from the same document.
+///
+/// Documents are compared by their contents, rather than modification times,
+/// which some file systems only keep to the nearest few milliseconds.
+pub fn read(path: &Path, date: NaiveDate, doc: &str) -> Option<Plan> {
+    let text = fs::read_to_string(path).ok()?;
+    let cached: CachedPlan = serde_json::from_str(&text).ok()?;
+
+    if cached.date == date && cached.hash == hash(doc) {
+        Some(cached.plan)
+    } else {
+        None
+    }
+}
+
+/// Caches the plan for a date.
+pub fn write(path: &Path, date: NaiveDate, doc: &str, plan: &Plan) -> io::Result<()> {
+    let cached = CachedPlan {
+        date,
+        hash: hash(doc),
+        plan: plan.clone(),
+    };
+
+    create_parent_dir(path)?;
+    write_atomic(path, &serde_json::to_string(&cached)?)
+}
+
+/// Hashes a document with 64-bit FNV-1a.  Unlike the standard library's
+/// hasher, its output doesn't change between Rust versions, so the cache stays
+/// valid across builds.
+fn hash(doc: &str) -> u64 {
+    const OFFSET_BASIS: u64 = 0xcbf2_9ce4_8422_2325;
+    const PRIME: u64 = 0x0100_0000_01b3;
+
+    doc.bytes().fold(OFFSET_BASIS, |hash, byte| {
+        (hash ^ u64::from(byte)).wrapping_mul(PRIME)
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn hash_is_stable() {
+        assert_eq!(hash(""), 0xcbf2_9ce4_8422_2325);
+        assert_eq!(hash("a"), 0xaf63_dc4c_8601_ec8c);
+    }
+}
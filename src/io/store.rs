@@ -0,0 +1,51 @@
+mod fs;
+mod memory;
+
+use std::{io, path::PathBuf, time::SystemTime};
+
+use chrono::NaiveDate;
+
+pub use self::fs::FsStore;
+pub use self::memory::MemoryStore;
+
+/// Metadata of a stored plan.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Metadata {
+    /// When the plan was last modified, if the store knows.
+    pub modified: Option<SystemTime>,
+    /// The size of the plan in bytes.
+    pub len: u64,
+}
+
+/// Storage for plan documents, keyed by date.
+///
+/// Reading a date without a plan fails with [io::ErrorKind::NotFound].
+pub trait PlanStore: Sync {
+    /// Lists the dates that have a plan, oldest first.
+    fn list_dates(&self) -> io::Result<Vec<NaiveDate>>;
+
+    /// Reads the plan for a date.
+    fn read(&self, date: NaiveDate) -> io::Result<String>;
+
+    /// Writes the plan for a date, replacing any existing plan.
+    fn write(&self, date: NaiveDate, contents: &str) -> io::Result<()>;
+
+    /// Writes the plan for a date, failing with
+    /// [io::ErrorKind::AlreadyExists] if there already is one.
+    fn create(&self, date: NaiveDate, contents: &str) -> io::Result<()>;
+
+    /// Deletes the plan for a date.
+    fn delete(&self, date: NaiveDate) -> io::Result<()>;
+
+    /// Gets the metadata of the plan for a date.
+    fn metadata(&self, date: NaiveDate) -> io::Result<Metadata>;
+
+    /// Gets the path of the plan for a date, used in messages and to open the
+    /// plan in an editor.
+    fn path(&self, date: NaiveDate) -> PathBuf;
+
+    /// Reads the template new plans are created from, if the store has one.
+    fn read_template(&self) -> io::Result<Option<String>> {
+        Ok(None)
+    }
+}
@@ -0,0 +1,182 @@
+use std::{
+    fs, io,
+    path::{Path, PathBuf},
+};
+
+use chrono::NaiveDate;
+
+use super::{Metadata, PlanStore};
+use crate::io::{create_parent_dir, index::Lister, write_atomic, write_new, FilePattern, History};
+use crate::template::TEMPLATE_FILE;
+
+const DEFAULT_HISTORY_RETENTION: usize = 10;
+
+/// A [PlanStore] backed by a directory of plan files, named after a
+/// [FilePattern].
+///
+/// Writes are atomic, and replaced plans are saved to the [History].
+#[derive(Debug)]
+pub struct FsStore {
+    path: PathBuf,
+    pattern: FilePattern,
+    history: History,
+    index_cache: bool,
+}
+
+impl FsStore {
+    pub fn new(path: PathBuf, pattern: FilePattern) -> Self {
+        log::debug!(
+            "Initializing plan directory at '{:#?}' with pattern `{}`.",
+            path,
+            pattern.as_str()
+        );
+        let history = History::new(&path, DEFAULT_HISTORY_RETENTION);
+        FsStore {
+            path,
+            pattern,
+            history,
+            index_cache: false,
+        }
+    }
+
+    /// Sets whether directory listings are cached in `.cache/index`, so that
+    /// unchanged folders of a large plan archive aren't read again.
+    pub fn with_index_cache(mut self, index_cache: bool) -> Self {
+        self.index_cache = index_cache;
+        self
+    }
+
+    /// Sets how many previous versions of each plan are kept.
+    pub fn with_history_retention(mut self, retention: usize) -> Self {
+        self.history = History::new(&self.path, retention);
+        self
+    }
+
+    /// Get the path of the plan directory.
+    pub fn dir(&self) -> &Path {
+        &self.path
+    }
+
+    /// Get the history of previous plan versions.
+    pub fn history(&self) -> &History {
+        &self.history
+    }
+
+    /// Gets a path relative to the plan directory.
+    pub fn relative_path<'a>(&self, path: &'a Path) -> &'a Path {
+        path.strip_prefix(&self.path).unwrap_or(path)
+    }
+
+    /// Writes a new file, failing if it already exists.
+    pub fn write_new(&self, path: &Path, contents: &str) -> io::Result<()> {
+        create_parent_dir(path)?;
+        write_new(path, contents)
+    }
+
+    /// Overwrites a file, saving its previous contents to the history.
+    pub fn write_file(&self, path: &Path, contents: &str) -> io::Result<()> {
+        match fs::read_to_string(path) {
+            Ok(previous) => self.history.save(self.relative_path(path), &previous)?,
+            Err(e) if e.kind() == io::ErrorKind::NotFound => create_parent_dir(path)?,
+            Err(e) => return Err(e),
+        }
+
+        write_atomic(path, contents)
+    }
+
+    /// Lists the plan files in the directory, sorted by the date in their
+    /// path.
+    pub fn get_files(&self) -> io::Result<Vec<(NaiveDate, PathBuf)>> {
+        let mut lister = Lister::new(&self.path, self.index_cache);
+        let mut files = Vec::new();
+        self.find_files(&mut lister, &self.path, self.pattern.depth(), &mut files)?;
+
+        if let Err(e) = lister.save() {
+            log::warn!("Could not save the index cache: {}", e);
+        }
+
+        files.sort();
+        Ok(files)
+    }
+
+    fn find_files(
+        &self,
+        lister: &mut Lister,
+        dir: &Path,
+        depth: usize,
+        files: &mut Vec<(NaiveDate, PathBuf)>,
+    ) -> io::Result<()> {
+        let listing = lister.list(dir)?;
+
+        if depth > 0 {
+            for subdir in listing.dirs {
+                self.find_files(lister, &dir.join(subdir), depth - 1, files)?;
+            }
+        }
+
+        for file in listing.files {
+            let path = dir.join(file);
+            match self.pattern.parse(self.relative_path(&path)) {
+                Some(date) => files.push((date, path)),
+                None => log::trace!("{:#?} is not a plan file", path),
+            }
+        }
+
+        Ok(())
+    }
+}
+
+impl PlanStore for FsStore {
+    fn list_dates(&self) -> io::Result<Vec<NaiveDate>> {
+        let mut dates: Vec<NaiveDate> = self
+            .get_files()?
+            .into_iter()
+            .filter(|(date, path)| *path == self.path(*date))
+            .map(|(date, _)| date)
+            .collect();
+        dates.dedup();
+        Ok(dates)
+    }
+
+    fn read(&self, date: NaiveDate) -> io::Result<String> {
+        fs::read_to_string(self.path(date))
+    }
+
+    fn write(&self, date: NaiveDate, contents: &str) -> io::Result<()> {
+        self.write_file(&self.path(date), contents)
+    }
+
+    fn create(&self, date: NaiveDate, contents: &str) -> io::Result<()> {
+        self.write_new(&self.path(date), contents)
+    }
+
+    fn delete(&self, date: NaiveDate) -> io::Result<()> {
+        let path = self.path(date);
+        self.history
+            .save(self.relative_path(&path), &fs::read_to_string(&path)?)?;
+        fs::remove_file(path)
+    }
+
+    fn metadata(&self, date: NaiveDate) -> io::Result<Metadata> {
+        let metadata = fs::metadata(self.path(date))?;
+        Ok(Metadata {
+            modified: metadata.modified().ok(),
+            len: metadata.len(),
+        })
+    }
+
+    fn path(&self, date: NaiveDate) -> PathBuf {
+        self.path.join(self.pattern.path_for(date))
+    }
+
+    fn read_template(&self) -> io::Result<Option<String>> {
+        let template_path = self.path.join(TEMPLATE_FILE);
+
+        if template_path.exists() {
+            log::trace!("Using template at path: {:#?}", template_path);
+            fs::read_to_string(template_path).map(Some)
+        } else {
+            Ok(None)
+        }
+    }
+}
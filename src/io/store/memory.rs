@@ -0,0 +1,102 @@
+use std::{collections::BTreeMap, io, path::PathBuf, sync::RwLock, time::SystemTime};
+
+use chrono::NaiveDate;
+
+use super::{Metadata, PlanStore};
+
+#[derive(Debug, Clone)]
+struct StoredPlan {
+    contents: String,
+    modified: SystemTime,
+}
+
+/// A [PlanStore] that keeps plans in memory, for tests and tools that don't
+/// want to touch the file system.
+#[derive(Debug, Default)]
+pub struct MemoryStore {
+    plans: RwLock<BTreeMap<NaiveDate, StoredPlan>>,
+    template: Option<String>,
+}
+
+impl MemoryStore {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Sets the template new plans are created from.
+    pub fn with_template(mut self, template: &str) -> Self {
+        self.template = Some(template.to_string());
+        self
+    }
+}
+
+fn not_found(date: NaiveDate) -> io::Error {
+    io::Error::new(
+        io::ErrorKind::NotFound,
+        format!("No plan for {}", date.format("%Y-%m-%d")),
+    )
+}
+
+impl PlanStore for MemoryStore {
+    fn list_dates(&self) -> io::Result<Vec<NaiveDate>> {
+        Ok(self.plans.read().unwrap().keys().copied().collect())
+    }
+
+    fn read(&self, date: NaiveDate) -> io::Result<String> {
+        match self.plans.read().unwrap().get(&date) {
+            Some(plan) => Ok(plan.contents.to_owned()),
+            None => Err(not_found(date)),
+        }
+    }
+
+    fn write(&self, date: NaiveDate, contents: &str) -> io::Result<()> {
+        let plan = StoredPlan {
+            contents: contents.to_string(),
+            modified: SystemTime::now(),
+        };
+        self.plans.write().unwrap().insert(date, plan);
+        Ok(())
+    }
+
+    fn create(&self, date: NaiveDate, contents: &str) -> io::Result<()> {
+        let mut plans = self.plans.write().unwrap();
+        if plans.contains_key(&date) {
+            return Err(io::Error::new(
+                io::ErrorKind::AlreadyExists,
+                format!("A plan for {} already exists", date.format("%Y-%m-%d")),
+            ));
+        }
+
+        let plan = StoredPlan {
+            contents: contents.to_string(),
+            modified: SystemTime::now(),
+        };
+        plans.insert(date, plan);
+        Ok(())
+    }
+
+    fn delete(&self, date: NaiveDate) -> io::Result<()> {
+        match self.plans.write().unwrap().remove(&date) {
+            Some(_) => Ok(()),
+            None => Err(not_found(date)),
+        }
+    }
+
+    fn metadata(&self, date: NaiveDate) -> io::Result<Metadata> {
+        match self.plans.read().unwrap().get(&date) {
+            Some(plan) => Ok(Metadata {
+                modified: Some(plan.modified),
+                len: plan.contents.len() as u64,
+            }),
+            None => Err(not_found(date)),
+        }
+    }
+
+    fn path(&self, date: NaiveDate) -> PathBuf {
+        PathBuf::from(format!("memory/{}", date.format("%Y-%m-%d")))
+    }
+
+    fn read_template(&self) -> io::Result<Option<String>> {
+        Ok(self.template.to_owned())
+    }
+}
@@ -0,0 +1,42 @@
+//! Parse, edit and store plaintext daily plans.
+//!
+//! A [Plan] is a markdown document with a date header, a task list and a
+//! schedule.  Plans are kept in a [PlanDirectory], one per date, backed by a
+//! [PlanStore](io::PlanStore) such as a folder of files ([FsStore](io::FsStore))
+//! or memory ([MemoryStore](io::MemoryStore)).
+//!
+//! ```
+//! use chrono::{NaiveDate, NaiveTime};
+//! use planner::{io::MemoryStore, Event, PlanDirectory, ScheduleSection, Task, TaskStatus};
+//!
+//! let plan_dir = PlanDirectory::with_store(MemoryStore::new());
+//! let date = NaiveDate::from_ymd(2021, 6, 1);
+//!
+//! let mut file = plan_dir.get_or_create_plan(date).unwrap();
+//! let mut plan = file.plan().clone();
+//! if let Some(tasks) = plan.tasks_mut() {
+//!     let work = tasks.category_mut("Work");
+//!     work.tasks_mut()
+//!         .push(Task::new("Review pull requests", TaskStatus::Incomplete));
+//! }
+//! if let Some(schedule) = plan.schedule_mut() {
+//!     let standup = Event::new(NaiveTime::from_hms(9, 30, 0), "Standup");
+//!     let day_start = NaiveTime::from_hms(0, 0, 0);
+//!     schedule.add_event(ScheduleSection::Planned, standup, date, day_start);
+//! }
+//!
+//! file.write(&plan_dir, &plan.to_markdown()).unwrap();
+//! ```
+
+pub mod clock;
+pub mod io;
+pub mod plan;
+pub mod template;
+
+pub use crate::io::{PlanDirectory, PlanError, PlanFile};
+pub use crate::plan::{
+    format::PlanFormat,
+    schedule::{Event, Schedule, ScheduleSection},
+    tasks::{Task, TaskCategory, TaskList, TaskStatus},
+    Plan,
+};
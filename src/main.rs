@@ -1,40 +1,153 @@
 mod cli;
-mod io;
-mod plan;
+mod commands;
+mod config;
+
+use std::io::BufRead;
 
 use clap::Clap;
 
+use planner::clock::Clock;
+use planner::io::{self, PlanDirectory, PlanError, PlanFile};
+
 use crate::cli::Options;
-use crate::io::*;
+use crate::config::Config;
 
 fn main() -> anyhow::Result<()> {
     env_logger::init();
     let options = Options::parse();
+    let config = Config::load()?;
+    let clock = options.clock()?.with_day_start(config.day_start()?);
+    let mut plan_dir = PlanDirectory::new(options.get_root_dir(&config)?, config.file_pattern()?)
+        .with_index_cache(config.index_cache);
+    if let Some(retention) = config.history_retention {
+        plan_dir = plan_dir.with_history_retention(retention);
+    }
 
-    let plan_dir = PlanDirectory::new(options.get_root_dir());
-    let today = chrono::Local::today().naive_local();
-    let today_plan = plan_dir.get_plan(today);
-
-    let today_plan = match today_plan {
-        Some(p) => p,
-        None => {
-            let most_recent_plan = plan_dir.get_most_recent_plan();
-            match most_recent_plan {
-                Some(p) => plan_dir.copy_plan(p, today)?,
-                None => plan_dir.create_plan(today)?,
+    match options.command() {
+        cli::Command::View(view) => {
+            let today_plan = get_today_plan(&plan_dir, clock, &config)?;
+            commands::view::run(today_plan.plan(), &view, clock)?;
+        }
+        cli::Command::Edit => match get_today_plan(&plan_dir, clock, &config) {
+            Ok(today_plan) => today_plan.edit(),
+            Err(e) => match e.downcast_ref::<PlanError>() {
+                Some(PlanError::Unparseable { path, .. }) => edit_broken_plan(path, &e)?,
+                _ => return Err(e),
+            },
+        },
+        cli::Command::Today(today) => {
+            if today.all {
+                commands::today::run_all(&config, clock.today())?;
+            } else {
+                let today_plan = get_today_plan(&plan_dir, clock, &config)?;
+                print!("{}", today_plan.plan().to_markdown());
             }
         }
-    };
-
-    match options.command() {
-        cli::Command::View => {
-            let parsed = today_plan.plan().to_markdown();
-            print!("{}", parsed);
+        cli::Command::Doctor(doctor) => {
+            cli::check_root_dir(plan_dir.path())?;
+            commands::doctor::run(&plan_dir, doctor.fix, doctor.yes)?;
+        }
+        cli::Command::Undo(undo) => {
+            cli::check_root_dir(plan_dir.path())?;
+            let date = undo.date.unwrap_or_else(|| clock.today());
+            commands::history::undo(&plan_dir, date)?;
+        }
+        cli::Command::Redo(redo) => {
+            cli::check_root_dir(plan_dir.path())?;
+            let date = redo.date.unwrap_or_else(|| clock.today());
+            commands::history::redo(&plan_dir, date)?;
+        }
+        cli::Command::History(history) => {
+            cli::check_root_dir(plan_dir.path())?;
+            let date = history.date.unwrap_or_else(|| clock.today());
+            commands::history::list(&plan_dir, date)?;
+        }
+        cli::Command::Export(export) => {
+            cli::check_root_dir(plan_dir.path())?;
+            commands::export::run(&plan_dir, &export, clock.day_start())?;
+        }
+        cli::Command::ImportIcs(import) => {
+            cli::check_root_dir(plan_dir.path())?;
+            let date = import.date.unwrap_or_else(|| clock.today());
+            commands::import_ics::run(&plan_dir, &import.file, date, clock.day_start())?;
+        }
+        cli::Command::Publish(publish) => {
+            cli::check_root_dir(plan_dir.path())?;
+            commands::publish::run(&plan_dir, &publish.out, clock.day_start())?;
+        }
+        cli::Command::Todotxt(todotxt) => {
+            cli::check_root_dir(plan_dir.path())?;
+            match todotxt.command {
+                cli::TodotxtCommand::Import(import) => {
+                    let date = import.date.unwrap_or_else(|| clock.today());
+                    commands::todotxt::import(&plan_dir, &import.file, date)?;
+                }
+                cli::TodotxtCommand::Export(export) => {
+                    let date = export.date.unwrap_or_else(|| clock.today());
+                    commands::todotxt::export(&plan_dir, date)?;
+                }
+            }
         }
-        cli::Command::Edit => {
-            today_plan.edit();
+        cli::Command::Taskwarrior(taskwarrior) => {
+            cli::check_root_dir(plan_dir.path())?;
+            match taskwarrior.command {
+                cli::TaskwarriorCommand::Import(import) => {
+                    let date = import.date.unwrap_or_else(|| clock.today());
+                    commands::taskwarrior::import(&plan_dir, &import.file, date)?;
+                }
+                cli::TaskwarriorCommand::Export(export) => {
+                    let date = export.date.unwrap_or_else(|| clock.today());
+                    commands::taskwarrior::export(&plan_dir, date)?;
+                }
+            }
+        }
+        cli::Command::Now(now) => {
+            cli::check_root_dir(plan_dir.path())?;
+            commands::now::now(&plan_dir, clock, now.short)?;
+        }
+        cli::Command::Next(next) => {
+            cli::check_root_dir(plan_dir.path())?;
+            commands::now::next(&plan_dir, clock, next.short)?;
+        }
+        cli::Command::Init(init) => {
+            commands::init::run(&plan_dir, init.import.as_deref())?;
         }
     }
 
     Ok(())
 }
+
+/// Gets today's plan, creating it from the most recent plan if necessary.  New
+/// plans get the events from the configured calendar file.
+fn get_today_plan(
+    plan_dir: &PlanDirectory,
+    clock: Clock,
+    config: &Config,
+) -> anyhow::Result<PlanFile> {
+    cli::check_root_dir(plan_dir.path())?;
+
+    let today = clock.today();
+    let is_new = matches!(plan_dir.get_plan(today), Err(PlanError::Missing(_)));
+    let mut today_plan = plan_dir.get_or_create_plan(today)?;
+
+    if let (true, Some(calendar)) = (is_new, &config.import_ics) {
+        let day_start = clock.day_start();
+        if let Err(e) = commands::import_ics::import(plan_dir, &mut today_plan, calendar, day_start)
+        {
+            eprintln!("Could not import events from {:?}: {:#}", calendar, e);
+        }
+    }
+
+    Ok(today_plan)
+}
+
+/// Shows why a plan couldn't be parsed, then opens it in the editor so it can
+/// be fixed.
+fn edit_broken_plan(path: &std::path::Path, error: &anyhow::Error) -> anyhow::Result<()> {
+    eprintln!("{:#}", error);
+    eprintln!("Press Enter to open the plan in the editor...");
+    std::io::stdin().lock().read_line(&mut String::new())?;
+
+    io::edit(path);
+    Ok(())
+}
@@ -1,5 +1,8 @@
-mod schedule;
-mod tasks;
+pub mod format;
+pub mod schedule;
+pub mod tasks;
+pub mod taskwarrior;
+pub mod todotxt;
 mod util;
 use std::fmt::Write;
 
@@ -8,23 +11,27 @@ use crate::plan::tasks::parse_task_list;
 use self::{tasks::TaskList, util::get_node_text};
 use chrono::{Datelike, NaiveDate, Weekday};
 use comrak::{nodes::NodeValue, parse_document, Arena, ComrakOptions};
+use serde::{Deserialize, Serialize};
 
 use self::schedule::{parse_schedule, Schedule};
 
-#[derive(Debug, Clone)]
+/// A plan for a single day.
+#[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct Plan {
     date: NaiveDate,
     tasks: Option<TaskList>,
     schedule: Option<Schedule>,
+    #[serde(default)]
+    notes: String,
 }
 impl Plan {
-    #[cfg(test)]
     /// Initializes a new plan.
-    fn new(date: NaiveDate, tasks: Option<TaskList>, schedule: Option<Schedule>) -> Self {
+    pub fn new(date: NaiveDate, tasks: Option<TaskList>, schedule: Option<Schedule>) -> Self {
         Self {
             date,
             tasks,
             schedule,
+            notes: String::new(),
         }
     }
 
@@ -80,8 +87,11 @@ impl Plan {
 
                     let header_text = get_node_text(node);
 
-                    if header_text.trim().to_lowercase() == "tasks" {
-                        parse_state = ParseState::TaskSectionStart
+                    // A plan without tasks can still have a schedule.
+                    match header_text.trim().to_lowercase().as_str() {
+                        "tasks" => parse_state = ParseState::TaskSectionStart,
+                        "schedule" => parse_state = ParseState::ScheduleSectionStart,
+                        _ => {}
                     }
                     continue;
                 }
@@ -136,6 +146,7 @@ impl Plan {
             date,
             tasks,
             schedule,
+            notes: parse_notes(doc),
         })
     }
 
@@ -145,7 +156,7 @@ impl Plan {
     pub fn day(&self) -> &'static str {
         match self.date.weekday() {
             Weekday::Mon => "Monday",
-            Weekday::Tue => "Tuseday",
+            Weekday::Tue => "Tuesday",
             Weekday::Wed => "Wednesday",
             Weekday::Thu => "Thursday",
             Weekday::Fri => "Friday",
@@ -158,12 +169,32 @@ impl Plan {
         &self.tasks
     }
 
+    /// Get a mutable reference to the plan's tasks.
+    pub fn tasks_mut(&mut self) -> &mut Option<TaskList> {
+        &mut self.tasks
+    }
+
     /// Get a reference to the plan's schedule.
     pub fn schedule(&self) -> &Option<Schedule> {
         &self.schedule
     }
 
-    /// Exports a copy of the plan as markdown.
+    /// Get a mutable reference to the plan's schedule.
+    pub fn schedule_mut(&mut self) -> &mut Option<Schedule> {
+        &mut self.schedule
+    }
+
+    /// Get the plan's notes, as markdown.
+    pub fn notes(&self) -> &str {
+        &self.notes
+    }
+
+    pub fn set_notes(&mut self, notes: &str) {
+        self.notes = notes.trim().to_string();
+    }
+
+    /// Exports a copy of the plan as markdown, including completed tasks and
+    /// notes.  Use [Plan::rollover] first for the next day's plan.
     pub fn to_markdown(&self) -> String {
         let mut md: String = String::new();
 
@@ -176,12 +207,11 @@ impl Plan {
             for category in tasks.categories() {
                 writeln!(md, "- **{}**", category.name()).unwrap();
                 for task in category.tasks() {
-                    match task.status() {
-                        self::tasks::TaskStatus::Incomplete => {
-                            writeln!(md, "  - [ ] {}", task.description()).unwrap();
-                        }
-                        self::tasks::TaskStatus::Complete => {}
-                    }
+                    let mark = match task.status() {
+                        self::tasks::TaskStatus::Incomplete => " ",
+                        self::tasks::TaskStatus::Complete => "x",
+                    };
+                    writeln!(md, "  - [{}] {}", mark, task.description()).unwrap();
                 }
             }
             writeln!(md).unwrap();
@@ -203,17 +233,31 @@ impl Plan {
             }
         }
         writeln!(md, "## Notes").unwrap();
-        writeln!(md).unwrap();
+        if self.notes.is_empty() {
+            writeln!(md).unwrap();
+        } else {
+            writeln!(md, "{}", self.notes).unwrap();
+        }
 
         md
     }
 
     /// Sets the plan date.
-    pub(crate) fn set_date(&mut self, date: NaiveDate) {
+    pub fn set_date(&mut self, date: NaiveDate) {
         self.date = date;
     }
 
-    /// Cleans the plan and its subsections.
+    /// Gets the plan that this plan rolls over into on a later date: a clean
+    /// copy (see [Plan::clean]) with the new date.
+    pub fn rollover(&self, date: NaiveDate) -> Plan {
+        let mut plan = self.clone();
+        plan.clean();
+        plan.set_date(date);
+        plan
+    }
+
+    /// Cleans the plan and its subsections, removing completed tasks, actual
+    /// events and notes.
     pub fn clean(&mut self) {
         log::trace!("Cleaning plan `{:#?}`...", self.date());
 
@@ -227,11 +271,27 @@ impl Plan {
             schedule.clean();
             log::trace!("Schedule section cleaned.");
         }
+        self.notes.clear();
 
         log::trace!("Plan cleaned.");
     }
 }
 
+/// Gets the text of the `## Notes` section, up to the next heading of the same
+/// or a higher level.
+fn parse_notes(doc: &str) -> String {
+    let is_heading = |l: &str| l.starts_with("# ") || l.starts_with("## ");
+
+    let notes: Vec<&str> = doc
+        .lines()
+        .skip_while(|l| !(l.starts_with("## ") && l[3..].trim().eq_ignore_ascii_case("notes")))
+        .skip(1)
+        .take_while(|l| !is_heading(l))
+        .collect();
+
+    notes.join("\n").trim().to_string()
+}
+
 #[cfg(test)]
 mod tests {
     use crate::plan::tasks::TaskStatus;
@@ -286,4 +346,71 @@ mod tests {
         assert_eq!(already_done.description(), "Already done");
         assert_eq!(already_done.status(), &TaskStatus::Complete);
     }
+
+    #[test]
+    fn import_schedule_without_tasks() {
+        let md = indoc! {"
+            # 06/01/2021
+            Tuesday
+
+            ## Schedule
+            - **Planned**
+              - 0930\tStandup
+        "};
+
+        let plan = Plan::from_markdown(md).unwrap();
+        assert!(plan.tasks().is_none());
+        assert_eq!(plan.schedule().as_ref().unwrap().planned().len(), 1);
+    }
+
+    #[test]
+    fn export_md_keeps_everything() {
+        let md = indoc! {"
+            # 06/01/2021
+            Tuesday
+
+            ## Tasks
+            - **Work**
+              - [x] Write report
+              - [ ] Call the bank
+
+            ## Schedule
+            - **Planned**
+              - 0930\tStandup
+            - **Actual**
+              - 0945\tStandup
+            ## Notes
+            Remember the milk.
+
+            ### Ideas
+            - A bigger desk
+        "};
+
+        let plan = Plan::from_markdown(md).unwrap();
+        assert_eq!(
+            plan.notes(),
+            "Remember the milk.\n\n### Ideas\n- A bigger desk"
+        );
+        assert_eq!(plan.to_markdown(), md);
+
+        let next = plan.rollover(NaiveDate::from_ymd(2021, 6, 2));
+        assert_eq!(
+            next.to_markdown(),
+            indoc! {"
+                # 06/02/2021
+                Wednesday
+
+                ## Tasks
+                - **Work**
+                  - [ ] Call the bank
+
+                ## Schedule
+                - **Planned**
+                  - 0930\tStandup
+                - **Actual**
+                ## Notes
+
+            "}
+        );
+    }
 }
@@ -0,0 +1,263 @@
+//! Plan file formats.
+
+mod markdown;
+mod org;
+
+use std::path::Path;
+
+use anyhow::Context;
+use chrono::{NaiveDate, NaiveTime};
+
+use super::{
+    schedule::{Event, Schedule, ScheduleSection},
+    tasks::{Task, TaskList},
+    Plan,
+};
+
+pub use self::org::Org;
+
+/// A file format that plans can be read from and written to.
+pub trait PlanFormat: Sync {
+    /// The name of the format, e.g. `markdown`.
+    fn name(&self) -> &'static str;
+
+    /// Parses a plan from a document.
+    fn parse(&self, doc: &str) -> anyhow::Result<Plan>;
+
+    /// Writes a plan as a document.
+    fn serialize(&self, plan: &Plan) -> String;
+
+    /// Gets the line after the date header of a document, which should hold
+    /// the weekday.
+    fn weekday_line<'a>(&self, doc: &'a str) -> Option<&'a str>;
+
+    /// Rewrites the date header and weekday of a plan document, leaving the
+    /// rest of the document untouched.
+    fn rewrite_header(&self, doc: &str, date: NaiveDate) -> String;
+
+    // The edits below rewrite the whole document by default.  Formats that
+    // can edit their documents in place override them, to keep the
+    // formatting and any text planner doesn't read.
+
+    /// Adds tasks to a category of a plan document, adding the category if
+    /// the plan doesn't have it.
+    fn insert_tasks(&self, doc: &str, category: &str, tasks: &[&Task]) -> anyhow::Result<String> {
+        rewrite(self, doc, |plan| {
+            plan.tasks_mut()
+                .get_or_insert_with(|| TaskList::new(Vec::new()))
+                .category_mut(category)
+                .tasks_mut()
+                .extend(tasks.iter().map(|&t| t.clone()));
+            Ok(())
+        })
+    }
+
+    /// Replaces a task in a category of a plan document.
+    fn replace_task(
+        &self,
+        doc: &str,
+        category: &str,
+        old: &Task,
+        new: &Task,
+    ) -> anyhow::Result<String> {
+        rewrite(self, doc, |plan| {
+            let task = plan
+                .tasks_mut()
+                .iter_mut()
+                .flat_map(|tasks| tasks.categories_mut())
+                .filter(|c| c.name().eq_ignore_ascii_case(category))
+                .flat_map(|c| c.tasks_mut())
+                .find(|t| t.description() == old.description())
+                .with_context(|| {
+                    format!(
+                        "Could not find task `{}` in {}",
+                        old.description(),
+                        category
+                    )
+                })?;
+            *task = new.clone();
+            Ok(())
+        })
+    }
+
+    /// Adds events to the planned schedule of a plan document, in order on
+    /// the plan's day, which starts at `day_start`.
+    fn insert_planned(
+        &self,
+        doc: &str,
+        events: &[Event],
+        day_start: NaiveTime,
+    ) -> anyhow::Result<String> {
+        rewrite(self, doc, |plan| {
+            let date = plan.date();
+            let schedule = plan
+                .schedule_mut()
+                .get_or_insert_with(|| Schedule::new(Vec::new(), Vec::new()));
+            for event in events {
+                schedule.add_event(ScheduleSection::Planned, event.clone(), date, day_start);
+            }
+            Ok(())
+        })
+    }
+}
+
+/// Edits a document by parsing the plan, changing it, and writing it back.
+fn rewrite<F, C>(format: &F, doc: &str, change: C) -> anyhow::Result<String>
+where
+    F: PlanFormat + ?Sized,
+    C: FnOnce(&mut Plan) -> anyhow::Result<()>,
+{
+    let mut plan = format.parse(doc)?;
+    change(&mut plan)?;
+    Ok(format.serialize(&plan))
+}
+
+/// The markdown format of `*.plan.md` files.
+#[derive(Debug, Clone, Copy, Default)]
+pub struct Markdown;
+
+impl PlanFormat for Markdown {
+    fn name(&self) -> &'static str {
+        "markdown"
+    }
+
+    fn parse(&self, doc: &str) -> anyhow::Result<Plan> {
+        Plan::from_markdown(doc)
+    }
+
+    fn serialize(&self, plan: &Plan) -> String {
+        plan.to_markdown()
+    }
+
+    fn weekday_line<'a>(&self, doc: &'a str) -> Option<&'a str> {
+        weekday_line(doc, '#')
+    }
+
+    fn rewrite_header(&self, doc: &str, date: NaiveDate) -> String {
+        rewrite_header(doc, date, '#')
+    }
+
+    fn insert_tasks(&self, doc: &str, category: &str, tasks: &[&Task]) -> anyhow::Result<String> {
+        markdown::insert_tasks(doc, category, tasks)
+    }
+
+    fn replace_task(
+        &self,
+        doc: &str,
+        category: &str,
+        old: &Task,
+        new: &Task,
+    ) -> anyhow::Result<String> {
+        markdown::replace_task(doc, category, old, new)
+    }
+
+    fn insert_planned(
+        &self,
+        doc: &str,
+        events: &[Event],
+        day_start: NaiveTime,
+    ) -> anyhow::Result<String> {
+        markdown::insert_planned(doc, events, day_start)
+    }
+}
+
+/// Gets the line after the date header, a top-level heading that starts with
+/// `marker`.
+fn weekday_line(doc: &str, marker: char) -> Option<&str> {
+    let is_header = |l: &str| l.strip_prefix(marker).is_some_and(|h| h.starts_with(' '));
+
+    doc.lines()
+        .skip_while(|l| !is_header(l))
+        .skip(1)
+        .find(|l| !l.trim().is_empty())
+        .filter(|l| !l.starts_with(marker))
+}
+
+/// Rewrites the date header, a top-level heading that starts with `marker`,
+/// and the weekday line after it.
+fn rewrite_header(doc: &str, date: NaiveDate, marker: char) -> String {
+    let is_header = |l: &str| l.strip_prefix(marker).is_some_and(|h| h.starts_with(' '));
+    let mut lines: Vec<String> = doc.lines().map(str::to_string).collect();
+
+    if let Some(header) = lines.iter().position(|l| is_header(l)) {
+        lines[header] = format!("{} {}", marker, date.format("%m/%d/%Y"));
+
+        let weekday = lines
+            .iter()
+            .skip(header + 1)
+            .position(|l| !l.trim().is_empty())
+            .map(|i| i + header + 1)
+            .filter(|&i| !lines[i].starts_with(marker));
+        if let Some(weekday) = weekday {
+            lines[weekday] = date.format("%A").to_string();
+        }
+    }
+
+    let mut rewritten = lines.join("\n");
+    if doc.ends_with('\n') {
+        rewritten.push('\n');
+    }
+    rewritten
+}
+
+/// Gets the format of a plan file from its extension: Org for `.org` files,
+/// and markdown for everything else.
+pub fn for_path(path: &Path) -> &'static dyn PlanFormat {
+    match path.extension().and_then(|e| e.to_str()) {
+        Some(e) if e.eq_ignore_ascii_case("org") => &Org,
+        _ => &Markdown,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use indoc::indoc;
+
+    #[test]
+    fn rewrites_header() {
+        let text = indoc! {"
+            # 01/01/2000
+            Saturday
+
+            ## Tasks
+            - **Personal**
+              - [ ] TODO
+        "};
+
+        assert_eq!(Markdown.weekday_line(text), Some("Saturday"));
+        assert_eq!(
+            Markdown.rewrite_header(text, NaiveDate::from_ymd(2000, 1, 4)),
+            indoc! {"
+                # 01/04/2000
+                Tuesday
+
+                ## Tasks
+                - **Personal**
+                  - [ ] TODO
+            "}
+        );
+    }
+
+    #[test]
+    fn rewrites_header_without_weekday() {
+        let text = "# 01/01/2000\n\n## Notes\n";
+
+        assert_eq!(Markdown.weekday_line(text), None);
+        assert_eq!(
+            Markdown.rewrite_header(text, NaiveDate::from_ymd(2000, 1, 4)),
+            "# 01/04/2000\n\n## Notes\n"
+        );
+    }
+
+    #[test]
+    fn rewrites_org_header() {
+        let text = "* 01/01/2000\nSaturday\n\n** Notes\n";
+
+        assert_eq!(Org.weekday_line(text), Some("Saturday"));
+        assert_eq!(
+            Org.rewrite_header(text, NaiveDate::from_ymd(2000, 1, 4)),
+            "* 01/04/2000\nTuesday\n\n** Notes\n"
+        );
+    }
+}
@@ -0,0 +1,447 @@
+//! In-place edits of markdown plan documents.
+
+use anyhow::Context;
+use chrono::NaiveTime;
+
+use crate::plan::{
+    schedule::{on_day, Event},
+    tasks::{parse_task_line, Task, TaskStatus},
+    Plan,
+};
+
+/// Adds tasks to a category of a plan document, adding the category (and the
+/// task list) if the plan doesn't have it.  The rest of the document is left
+/// untouched.
+pub(super) fn insert_tasks(text: &str, category: &str, tasks: &[&Task]) -> anyhow::Result<String> {
+    let mut lines: Vec<String> = text.lines().map(str::to_string).collect();
+
+    let (heading, section_end) = match find_section(&lines, "Tasks") {
+        Some(section) => section,
+        None => add_section(&mut lines, "Tasks", &["Schedule", "Notes"]),
+    };
+
+    let mut new_lines = Vec::new();
+    let insert_at = match find_category(&lines, heading, section_end, category) {
+        Some((_, items_end)) => items_end,
+        None => {
+            new_lines.push(format!("- **{}**", category));
+            lines[heading + 1..section_end]
+                .iter()
+                .rposition(|l| !l.trim().is_empty())
+                .map_or(heading + 1, |i| heading + 1 + i + 1)
+        }
+    };
+
+    for task in tasks {
+        let mark = match task.status() {
+            TaskStatus::Complete => "x",
+            TaskStatus::Incomplete => " ",
+        };
+        new_lines.push(format!("  - [{}] {}", mark, task.description()));
+    }
+
+    lines.splice(insert_at..insert_at, new_lines);
+
+    let mut inserted = lines.join("\n");
+    if text.ends_with('\n') {
+        inserted.push('\n');
+    }
+    Ok(inserted)
+}
+
+/// Replaces a task's line in a category of a plan document, leaving the rest
+/// of the document untouched.
+pub(super) fn replace_task(
+    text: &str,
+    category: &str,
+    old: &Task,
+    new: &Task,
+) -> anyhow::Result<String> {
+    let mut lines: Vec<String> = text.lines().map(str::to_string).collect();
+
+    let not_found = || {
+        format!(
+            "Could not find task `{}` in {}",
+            old.description(),
+            category
+        )
+    };
+    let (heading, section_end) = find_section(&lines, "Tasks").with_context(not_found)?;
+    let (label, items_end) =
+        find_category(&lines, heading, section_end, category).with_context(not_found)?;
+
+    let line = lines[label + 1..items_end]
+        .iter()
+        .position(|l| parse_task_line(l).is_some_and(|t| t.description() == old.description()))
+        .map(|i| label + 1 + i)
+        .with_context(not_found)?;
+
+    let indent_len = lines[line].len() - lines[line].trim_start().len();
+    let mark = match new.status() {
+        TaskStatus::Complete => "x",
+        TaskStatus::Incomplete => " ",
+    };
+    lines[line] = format!(
+        "{}- [{}] {}",
+        &lines[line][..indent_len],
+        mark,
+        new.description()
+    );
+
+    let mut replaced = lines.join("\n");
+    if text.ends_with('\n') {
+        replaced.push('\n');
+    }
+    Ok(replaced)
+}
+
+/// Inserts events into the planned schedule of a plan document, keeping the
+/// events in order on the plan's day, which starts at `day_start`, and the
+/// rest of the document untouched.  The schedule is added if the plan doesn't
+/// have one.
+pub(super) fn insert_planned(
+    text: &str,
+    events: &[Event],
+    day_start: NaiveTime,
+) -> anyhow::Result<String> {
+    let date = Plan::from_markdown(text)?.date();
+    let at = |time| on_day(date, day_start, time);
+    let mut lines: Vec<String> = text.lines().map(str::to_string).collect();
+
+    let (section, section_end) = match find_section(&lines, "Schedule") {
+        Some(section) => section,
+        None => add_section(&mut lines, "Schedule", &["Notes"]),
+    };
+    let label = lines[section + 1..section_end].iter().position(|l| {
+        let label = l.trim().trim_start_matches('-').trim().trim_matches('*');
+        !l.starts_with(char::is_whitespace) && label.eq_ignore_ascii_case("planned")
+    });
+    let heading = match label {
+        Some(i) => section + 1 + i,
+        None => {
+            lines.insert(section + 1, "- **Planned**".to_string());
+            section + 1
+        }
+    };
+
+    for event in events {
+        let items = lines[heading + 1..]
+            .iter()
+            .take_while(|l| l.starts_with(char::is_whitespace) && !l.trim().is_empty())
+            .count();
+        let indent = match lines.get(heading + 1) {
+            Some(l) if items > 0 => l[..l.len() - l.trim_start().len()].to_string(),
+            _ => "  ".to_string(),
+        };
+
+        let offset = lines[heading + 1..heading + 1 + items]
+            .iter()
+            .position(|l| get_item_start(l).is_some_and(|start| at(start) > at(*event.start())))
+            .unwrap_or(items);
+
+        lines.insert(
+            heading + 1 + offset,
+            format!(
+                "{}- {}\t{}",
+                indent,
+                event.start().format("%H%M"),
+                event.description()
+            ),
+        );
+    }
+
+    let mut inserted = lines.join("\n");
+    if text.ends_with('\n') {
+        inserted.push('\n');
+    }
+    Ok(inserted)
+}
+
+/// Finds a `## name` section of a document, and returns the line of its
+/// heading and the line after its end.
+fn find_section(lines: &[String], name: &str) -> Option<(usize, usize)> {
+    let heading = lines.iter().position(|l| {
+        l.strip_prefix("## ")
+            .is_some_and(|h| h.trim().eq_ignore_ascii_case(name))
+    })?;
+    let end = lines[heading + 1..]
+        .iter()
+        .position(|l| l.starts_with("# ") || l.starts_with("## "))
+        .map_or(lines.len(), |i| heading + 1 + i);
+
+    Some((heading, end))
+}
+
+/// Finds a category in the task list between `heading` and `section_end`, and
+/// returns the line of its label and the line after its last task.
+fn find_category(
+    lines: &[String],
+    heading: usize,
+    section_end: usize,
+    category: &str,
+) -> Option<(usize, usize)> {
+    let label = lines[heading + 1..section_end]
+        .iter()
+        .position(|l| {
+            let label = l.trim().trim_start_matches('-').trim().trim_matches('*');
+            !l.starts_with(char::is_whitespace) && label.eq_ignore_ascii_case(category)
+        })
+        .map(|i| heading + 1 + i)?;
+    let items = lines[label + 1..section_end]
+        .iter()
+        .take_while(|l| l.starts_with(char::is_whitespace) && !l.trim().is_empty())
+        .count();
+
+    Some((label, label + 1 + items))
+}
+
+/// Adds an empty `## name` section to a document, before the first of the
+/// `before` sections it has, or else at the end.  Returns the line of its
+/// heading and the line after its end.
+fn add_section(lines: &mut Vec<String>, name: &str, before: &[&str]) -> (usize, usize) {
+    let heading = match before.iter().find_map(|b| find_section(lines, b)) {
+        Some((next, _)) => {
+            lines.splice(next..next, vec![format!("## {}", name), String::new()]);
+            next
+        }
+        None => {
+            if lines.last().is_some_and(|l| !l.trim().is_empty()) {
+                lines.push(String::new());
+            }
+            lines.push(format!("## {}", name));
+            lines.len() - 1
+        }
+    };
+
+    (heading, heading + 1)
+}
+
+/// Gets the start time of a schedule item line, like `  - 0930\tStandup`.
+fn get_item_start(line: &str) -> Option<NaiveTime> {
+    let item = line.trim_start().strip_prefix("- ")?;
+    let time = item.split('\t').next()?;
+    NaiveTime::parse_from_str(time.trim(), "%H%M").ok()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use indoc::indoc;
+
+    #[test]
+    fn inserts_tasks() {
+        let text = indoc! {"
+            # 06/01/2021
+            Tuesday
+
+            ## Tasks
+            - **Work**
+              - [x] Write report
+
+            ## Notes
+            Remember the milk.
+        "};
+        let call = Task::new("(A) Call the bank #phone", TaskStatus::Incomplete);
+        let read = Task::new("Read a book", TaskStatus::Complete);
+
+        let text = insert_tasks(text, "Work", &[&call]).unwrap();
+        let text = insert_tasks(&text, "Home", &[&read]).unwrap();
+        assert_eq!(
+            text,
+            indoc! {"
+                # 06/01/2021
+                Tuesday
+
+                ## Tasks
+                - **Work**
+                  - [x] Write report
+                  - [ ] (A) Call the bank #phone
+                - **Home**
+                  - [x] Read a book
+
+                ## Notes
+                Remember the milk.
+            "}
+        );
+    }
+
+    #[test]
+    fn inserts_task_list() {
+        let text = indoc! {"
+            # 06/01/2021
+            Tuesday
+
+            ## Schedule
+            - **Planned**
+        "};
+        let call = Task::new("Call the bank", TaskStatus::Incomplete);
+
+        assert_eq!(
+            insert_tasks(text, "Inbox", &[&call]).unwrap(),
+            indoc! {"
+                # 06/01/2021
+                Tuesday
+
+                ## Tasks
+                - **Inbox**
+                  - [ ] Call the bank
+
+                ## Schedule
+                - **Planned**
+            "}
+        );
+    }
+
+    #[test]
+    fn replaces_task() {
+        let text = indoc! {"
+            # 06/01/2021
+            Tuesday
+
+            ## Tasks
+            - **Home**
+              - [ ] Call the bank
+            - **Work**
+              - [ ] Call the bank
+
+            ## Notes
+            Remember the milk.
+        "};
+        let old = Task::new("Call the bank", TaskStatus::Incomplete);
+        let new = Task::new("Call the bank uuid:5678", TaskStatus::Complete);
+
+        assert_eq!(
+            replace_task(text, "Work", &old, &new).unwrap(),
+            indoc! {"
+                # 06/01/2021
+                Tuesday
+
+                ## Tasks
+                - **Home**
+                  - [ ] Call the bank
+                - **Work**
+                  - [x] Call the bank uuid:5678
+
+                ## Notes
+                Remember the milk.
+            "}
+        );
+        assert!(replace_task(text, "Errands", &old, &new).is_err());
+    }
+
+    #[test]
+    fn replaces_formatted_task() {
+        let text = indoc! {"
+            # 06/01/2021
+            Tuesday
+
+            ## Tasks
+            - **Work**
+              - [X] Call *the bank* uuid:1234
+              - [ ] Read [the report](https://example.com)
+        "};
+        let plan = Plan::from_markdown(text).unwrap();
+        let tasks = plan.tasks().as_ref().unwrap().categories()[0].tasks();
+        assert_eq!(tasks[0].status(), &TaskStatus::Complete);
+
+        let read = Task::new("Read the report", TaskStatus::Complete);
+        let text = replace_task(text, "Work", &tasks[1], &read).unwrap();
+        let call = Task::new("Call the bank uuid:1234", TaskStatus::Incomplete);
+        let text = replace_task(&text, "Work", &tasks[0], &call).unwrap();
+        assert!(text.ends_with("  - [ ] Call the bank uuid:1234\n  - [x] Read the report\n"));
+    }
+
+    #[test]
+    fn inserts_sorted_events() {
+        let text = indoc! {"
+            # 06/01/2021
+            Tuesday
+
+            ## Tasks
+            - **Work**
+              - [x] Write report
+
+            ## Schedule
+            - **Planned**
+              - 0900\tStart the day
+              - 1200\tLunch
+            - **Actual**
+              - 0915\tStart the day
+
+            ## Notes
+            Remember the milk.
+        "};
+        let events = vec![
+            Event::new(NaiveTime::from_hms(8, 0, 0), "Gym"),
+            Event::new(NaiveTime::from_hms(9, 30, 0), "Standup"),
+            Event::new(NaiveTime::from_hms(13, 0, 0), "1:1"),
+        ];
+        let midnight = NaiveTime::from_hms(0, 0, 0);
+
+        assert_eq!(
+            insert_planned(text, &events, midnight).unwrap(),
+            indoc! {"
+                # 06/01/2021
+                Tuesday
+
+                ## Tasks
+                - **Work**
+                  - [x] Write report
+
+                ## Schedule
+                - **Planned**
+                  - 0800\tGym
+                  - 0900\tStart the day
+                  - 0930\tStandup
+                  - 1200\tLunch
+                  - 1300\t1:1
+                - **Actual**
+                  - 0915\tStart the day
+
+                ## Notes
+                Remember the milk.
+            "}
+        );
+
+        // After midnight is the end of a day that starts at 04:00.
+        let events = vec![Event::new(NaiveTime::from_hms(0, 30, 0), "Sleep")];
+        let inserted = insert_planned(text, &events, NaiveTime::from_hms(4, 0, 0)).unwrap();
+        assert!(inserted.contains("  - 1200\tLunch\n  - 0030\tSleep\n- **Actual**"));
+    }
+
+    #[test]
+    fn inserts_events_into_the_schedule() {
+        let text = indoc! {"
+            # 06/01/2021
+            Tuesday
+
+            ## Tasks
+            - **Planned**
+              - [ ] Trip
+
+            ## Notes
+            Remember the milk.
+        "};
+        let events = vec![Event::new(NaiveTime::from_hms(9, 30, 0), "Standup")];
+        let midnight = NaiveTime::from_hms(0, 0, 0);
+
+        assert_eq!(
+            insert_planned(text, &events, midnight).unwrap(),
+            indoc! {"
+                # 06/01/2021
+                Tuesday
+
+                ## Tasks
+                - **Planned**
+                  - [ ] Trip
+
+                ## Schedule
+                - **Planned**
+                  - 0930\tStandup
+
+                ## Notes
+                Remember the milk.
+            "}
+        );
+    }
+}
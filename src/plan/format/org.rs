@@ -0,0 +1,502 @@
+use std::fmt::Write;
+
+use anyhow::Context;
+use chrono::{NaiveDate, NaiveTime};
+
+use super::PlanFormat;
+use crate::plan::{
+    schedule::{on_day, Event, Schedule, ScheduleSection},
+    tasks::{Task, TaskCategory, TaskList, TaskStatus},
+    Plan,
+};
+
+/// The Org-mode format of `*.plan.org` files.
+///
+/// ```org
+/// * 06/01/2021
+/// Tuesday
+///
+/// ** Tasks
+/// *** Work
+/// **** TODO [#A] Call the bank                                     :phone:
+/// **** DONE Write report
+/// ** Schedule
+/// *** Planned
+/// - <2021-06-01 Tue 09:30> Standup
+/// *** Actual
+/// - <2021-06-01 Tue 09:45> Standup
+/// ** Notes
+/// ```
+///
+/// Priorities like `[#A]` and headline tags like `:phone:` are read as `(A)`
+/// and `#phone` in task descriptions.
+#[derive(Debug, Clone, Copy, Default)]
+pub struct Org;
+
+#[derive(Debug, PartialEq)]
+enum Section {
+    None,
+    Tasks,
+    Schedule(Option<ScheduleSection>),
+    Notes,
+    Other,
+}
+
+impl PlanFormat for Org {
+    fn name(&self) -> &'static str {
+        "org"
+    }
+
+    fn parse(&self, doc: &str) -> anyhow::Result<Plan> {
+        let mut date = None;
+        let mut tasks: Option<TaskList> = None;
+        let mut schedule: Option<Schedule> = None;
+        let mut notes = Vec::new();
+        let mut section = Section::None;
+
+        for line in doc.lines() {
+            let (level, headline) = match get_headline(line) {
+                Some((level, h)) => (level, Some(h)),
+                None => (0, None),
+            };
+
+            match (level, headline) {
+                (1, Some(h)) if date.is_none() => {
+                    date = Some(
+                        NaiveDate::parse_from_str(h, "%m/%d/%Y")
+                            .with_context(|| format!("Invalid date header `{}`", h))?,
+                    );
+                }
+                (2, Some(h)) => {
+                    section = match h.to_lowercase().as_str() {
+                        "tasks" => {
+                            tasks.get_or_insert_with(|| TaskList::new(Vec::new()));
+                            Section::Tasks
+                        }
+                        "schedule" => {
+                            schedule.get_or_insert_with(|| Schedule::new(Vec::new(), Vec::new()));
+                            Section::Schedule(None)
+                        }
+                        "notes" => Section::Notes,
+                        _ => Section::Other,
+                    }
+                }
+                _ if section == Section::Notes => notes.push(line),
+                (3, Some(h)) if section == Section::Tasks => {
+                    if let Some(tasks) = &mut tasks {
+                        tasks
+                            .categories_mut()
+                            .push(TaskCategory::new(h, Vec::new()));
+                    }
+                }
+                (3, Some(h)) if matches!(section, Section::Schedule(_)) => {
+                    section = match h.to_lowercase().as_str() {
+                        "planned" => Section::Schedule(Some(ScheduleSection::Planned)),
+                        "actual" => Section::Schedule(Some(ScheduleSection::Actual)),
+                        _ => anyhow::bail!("Expected 'Planned' or 'Actual', but found '{}'", h),
+                    }
+                }
+                (4, Some(h)) if section == Section::Tasks => {
+                    let category = tasks
+                        .as_mut()
+                        .and_then(|t| t.categories_mut().last_mut())
+                        .with_context(|| format!("Task `{}` has no category", h))?;
+                    category.tasks_mut().push(parse_task(h));
+                }
+                (_, None) => {
+                    if let Section::Schedule(Some(s)) = section {
+                        if let (Some(schedule), Some(event)) = (&mut schedule, parse_event(line)) {
+                            schedule.events_mut(s).push(event);
+                        }
+                    }
+                }
+                _ => {}
+            }
+        }
+
+        let date = date.context("The plan has no date header")?;
+        let mut plan = Plan::new(date, tasks, schedule);
+        plan.set_notes(&notes.join("\n"));
+        Ok(plan)
+    }
+
+    fn serialize(&self, plan: &Plan) -> String {
+        let mut org = String::new();
+
+        writeln!(org, "* {}", plan.date().format("%m/%d/%Y")).unwrap();
+        writeln!(org, "{}", plan.day()).unwrap();
+        writeln!(org).unwrap();
+
+        if let Some(tasks) = plan.tasks() {
+            writeln!(org, "** Tasks").unwrap();
+            for category in tasks.categories() {
+                writeln!(org, "*** {}", category.name()).unwrap();
+                for task in category.tasks() {
+                    writeln!(org, "**** {}", format_task(task)).unwrap();
+                }
+            }
+        }
+
+        if let Some(schedule) = plan.schedule() {
+            writeln!(org, "** Schedule").unwrap();
+            for &(section, name) in &[
+                (ScheduleSection::Planned, "Planned"),
+                (ScheduleSection::Actual, "Actual"),
+            ] {
+                writeln!(org, "*** {}", name).unwrap();
+                for event in schedule.events(section) {
+                    writeln!(
+                        org,
+                        "- <{} {}> {}",
+                        plan.date().format("%Y-%m-%d %a"),
+                        event.start().format("%H:%M"),
+                        event.description()
+                    )
+                    .unwrap();
+                }
+            }
+        }
+
+        writeln!(org, "** Notes").unwrap();
+        if !plan.notes().is_empty() {
+            writeln!(org, "{}", plan.notes()).unwrap();
+        }
+        org
+    }
+
+    fn weekday_line<'a>(&self, doc: &'a str) -> Option<&'a str> {
+        super::weekday_line(doc, '*')
+    }
+
+    fn rewrite_header(&self, doc: &str, date: NaiveDate) -> String {
+        super::rewrite_header(doc, date, '*')
+    }
+
+    fn insert_tasks(&self, doc: &str, category: &str, tasks: &[&Task]) -> anyhow::Result<String> {
+        let mut lines: Vec<String> = doc.lines().map(str::to_string).collect();
+
+        let (section, section_end) = match find_headline(&lines, 2, "Tasks", 0, lines.len()) {
+            Some(found) => found,
+            None => add_headline(&mut lines, 2, "Tasks", &["Schedule", "Notes"]),
+        };
+        let (heading, end) = match find_headline(&lines, 3, category, section, section_end) {
+            Some(found) => found,
+            None => {
+                let at = end_of_content(&lines, section, section_end);
+                lines.insert(at, format!("*** {}", category));
+                (at, at + 1)
+            }
+        };
+
+        let at = end_of_content(&lines, heading, end);
+        let new_lines = tasks.iter().map(|t| format!("**** {}", format_task(t)));
+        lines.splice(at..at, new_lines);
+
+        Ok(join_lines(lines, doc))
+    }
+
+    fn replace_task(
+        &self,
+        doc: &str,
+        category: &str,
+        old: &Task,
+        new: &Task,
+    ) -> anyhow::Result<String> {
+        let mut lines: Vec<String> = doc.lines().map(str::to_string).collect();
+
+        let line = find_headline(&lines, 2, "Tasks", 0, lines.len())
+            .and_then(|(section, end)| find_headline(&lines, 3, category, section, end))
+            .and_then(|(heading, end)| {
+                (heading + 1..end).find(|&i| {
+                    get_headline(&lines[i]).is_some_and(|(level, h)| {
+                        level == 4 && parse_task(h).description() == old.description()
+                    })
+                })
+            })
+            .with_context(|| {
+                format!(
+                    "Could not find task `{}` in {}",
+                    old.description(),
+                    category
+                )
+            })?;
+        lines[line] = format!("**** {}", format_task(new));
+
+        Ok(join_lines(lines, doc))
+    }
+
+    fn insert_planned(
+        &self,
+        doc: &str,
+        events: &[Event],
+        day_start: NaiveTime,
+    ) -> anyhow::Result<String> {
+        let date = self.parse(doc)?.date();
+        let mut lines: Vec<String> = doc.lines().map(str::to_string).collect();
+
+        let (section, section_end) = match find_headline(&lines, 2, "Schedule", 0, lines.len()) {
+            Some(found) => found,
+            None => add_headline(&mut lines, 2, "Schedule", &["Notes"]),
+        };
+        let (heading, mut end) = match find_headline(&lines, 3, "Planned", section, section_end) {
+            Some(found) => found,
+            None => {
+                lines.insert(section + 1, "*** Planned".to_string());
+                (section + 1, section + 2)
+            }
+        };
+
+        let on_day = |event: &Event| on_day(date, day_start, *event.start());
+        for event in events {
+            let at = (heading + 1..end)
+                .find(|&i| parse_event(&lines[i]).is_some_and(|e| on_day(&e) > on_day(event)))
+                .unwrap_or_else(|| end_of_content(&lines, heading, end));
+            lines.insert(
+                at,
+                format!(
+                    "- <{} {}> {}",
+                    date.format("%Y-%m-%d %a"),
+                    event.start().format("%H:%M"),
+                    event.description()
+                ),
+            );
+            end += 1;
+        }
+
+        Ok(join_lines(lines, doc))
+    }
+}
+
+/// Splits a headline like `** Tasks` into its level and text.
+fn get_headline(line: &str) -> Option<(usize, &str)> {
+    let level = line.chars().take_while(|&c| c == '*').count();
+    match line[level..].strip_prefix(' ') {
+        Some(h) if level > 0 => Some((level, h.trim())),
+        _ => None,
+    }
+}
+
+/// Finds a headline of a level between the lines `from` and `to`, and returns
+/// its line and the line after the end of its subtree.
+fn find_headline(
+    lines: &[String],
+    level: usize,
+    name: &str,
+    from: usize,
+    to: usize,
+) -> Option<(usize, usize)> {
+    let is_end = |l: &String| get_headline(l).is_some_and(|(lv, _)| lv <= level);
+
+    let heading = (from..to).find(|&i| {
+        get_headline(&lines[i]).is_some_and(|(lv, h)| lv == level && h.eq_ignore_ascii_case(name))
+    })?;
+    let end = (heading + 1..to).find(|&i| is_end(&lines[i])).unwrap_or(to);
+
+    Some((heading, end))
+}
+
+/// Adds a headline of a level before the first of the `before` headlines of
+/// the same level, or else at the end.  Returns its line and the line after
+/// the end of its subtree.
+fn add_headline(
+    lines: &mut Vec<String>,
+    level: usize,
+    name: &str,
+    before: &[&str],
+) -> (usize, usize) {
+    let heading = before
+        .iter()
+        .find_map(|b| find_headline(lines, level, b, 0, lines.len()))
+        .map_or(lines.len(), |(next, _)| next);
+    lines.insert(heading, format!("{} {}", "*".repeat(level), name));
+
+    (heading, heading + 1)
+}
+
+/// Gets the line after the last non-blank line of a subtree, where new
+/// entries are added.
+fn end_of_content(lines: &[String], heading: usize, end: usize) -> usize {
+    (heading + 1..end)
+        .rev()
+        .find(|&i| !lines[i].trim().is_empty())
+        .map_or(heading + 1, |i| i + 1)
+}
+
+fn join_lines(lines: Vec<String>, doc: &str) -> String {
+    let mut joined = lines.join("\n");
+    if doc.ends_with('\n') {
+        joined.push('\n');
+    }
+    joined
+}
+
+/// Parses a task headline like `TODO [#A] Call the bank :phone:`.
+fn parse_task(headline: &str) -> Task {
+    let (status, rest) = match headline.split_once(' ').unwrap_or((headline, "")) {
+        ("DONE", rest) => (TaskStatus::Complete, rest),
+        ("TODO", rest) => (TaskStatus::Incomplete, rest),
+        _ => (TaskStatus::Incomplete, headline),
+    };
+
+    let mut words: Vec<String> = rest.split_whitespace().map(str::to_string).collect();
+
+    if let Some(priority) = words
+        .first()
+        .and_then(|w| w.strip_prefix("[#")?.strip_suffix(']').map(str::to_string))
+    {
+        words[0] = format!("({})", priority);
+    }
+
+    let is_tags = |w: &String| w.len() > 2 && w.starts_with(':') && w.ends_with(':');
+    if let Some(tags) = words.last().filter(|w| is_tags(w)).cloned() {
+        words.pop();
+        words.extend(
+            tags.split(':')
+                .filter(|t| !t.is_empty())
+                .map(|t| format!("#{}", t)),
+        );
+    }
+
+    Task::new(&words.join(" "), status)
+}
+
+fn format_task(task: &Task) -> String {
+    let mut headline = match task.status() {
+        TaskStatus::Complete => "DONE".to_string(),
+        TaskStatus::Incomplete => "TODO".to_string(),
+    };
+
+    if let Some(priority) = task.priority() {
+        write!(headline, " [#{}]", priority).unwrap();
+    }
+
+    let text: Vec<&str> = task
+        .text()
+        .split_whitespace()
+        .filter(|w| !w.starts_with('#') || w.len() == 1)
+        .collect();
+    write!(headline, " {}", text.join(" ")).unwrap();
+
+    let tags = task.tags();
+    if !tags.is_empty() {
+        write!(headline, " :{}:", tags.join(":")).unwrap();
+    }
+
+    headline
+}
+
+/// Parses a schedule item like `- <2021-06-01 Tue 09:30> Standup`.
+fn parse_event(line: &str) -> Option<Event> {
+    let item = line.trim_start().strip_prefix("- ")?.trim_start();
+    let close = match item.chars().next()? {
+        '<' => '>',
+        '[' => ']',
+        _ => return None,
+    };
+
+    let (timestamp, description) = item[1..].split_once(close)?;
+    let time = timestamp.split_whitespace().last()?;
+    let start = NaiveTime::parse_from_str(time, "%H:%M").ok()?;
+
+    Some(Event::new(start, description.trim()))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use indoc::indoc;
+
+    #[test]
+    fn round_trip() {
+        let org = indoc! {"
+            * 06/01/2021
+            Tuesday
+
+            ** Tasks
+            *** Work
+            **** TODO [#A] Call the bank due:2021-06-02 :phone:
+            **** DONE Write report
+            ** Schedule
+            *** Planned
+            - <2021-06-01 Tue 09:30> Standup
+            - <2021-06-01 Tue 10:00> Focus time
+            *** Actual
+            - <2021-06-01 Tue 09:45> Standup
+            ** Notes
+            Remember the milk.
+        "};
+
+        let plan = Org.parse(org).unwrap();
+        assert_eq!(plan.date(), NaiveDate::from_ymd(2021, 6, 1));
+
+        let work = &plan.tasks().as_ref().unwrap().categories()[0];
+        assert_eq!(work.name(), "Work");
+        assert_eq!(
+            work.tasks()[0].description(),
+            "(A) Call the bank due:2021-06-02 #phone"
+        );
+        assert_eq!(work.tasks()[1].status(), &TaskStatus::Complete);
+
+        let schedule = plan.schedule().as_ref().unwrap();
+        assert_eq!(schedule.planned().len(), 2);
+        assert_eq!(schedule.actual()[0].start(), &NaiveTime::from_hms(9, 45, 0));
+
+        assert_eq!(Org.serialize(&plan), org);
+    }
+
+    #[test]
+    fn edits_in_place() {
+        let org = indoc! {"
+            * 06/01/2021
+            Tuesday
+
+            ** Tasks
+            *** Work
+            **** TODO Call the bank
+            SCHEDULED: <2021-06-01 Tue>
+            :PROPERTIES:
+            :EFFORT: 0:15
+            :END:
+            ** Schedule
+            *** Planned
+            - <2021-06-01 Tue 10:00> Focus time
+            ** Journal
+            A good day.
+        "};
+
+        let call = Task::new("Call the bank", TaskStatus::Incomplete);
+        let called = Task::new("Call the bank", TaskStatus::Complete);
+        let read = Task::new("Read a book", TaskStatus::Incomplete);
+        let standup = Event::new(NaiveTime::from_hms(9, 30, 0), "Standup");
+
+        let org = Org.replace_task(org, "Work", &call, &called).unwrap();
+        let org = Org.insert_tasks(&org, "Work", &[&read]).unwrap();
+        let org = Org.insert_tasks(&org, "Home", &[&read]).unwrap();
+        let midnight = NaiveTime::from_hms(0, 0, 0);
+        let org = Org.insert_planned(&org, &[standup], midnight).unwrap();
+        assert_eq!(
+            org,
+            indoc! {"
+                * 06/01/2021
+                Tuesday
+
+                ** Tasks
+                *** Work
+                **** DONE Call the bank
+                SCHEDULED: <2021-06-01 Tue>
+                :PROPERTIES:
+                :EFFORT: 0:15
+                :END:
+                **** TODO Read a book
+                *** Home
+                **** TODO Read a book
+                ** Schedule
+                *** Planned
+                - <2021-06-01 Tue 09:30> Standup
+                - <2021-06-01 Tue 10:00> Focus time
+                ** Journal
+                A good day.
+            "}
+        );
+    }
+}
@@ -1,20 +1,35 @@
-use chrono::NaiveTime;
+use chrono::{Duration, NaiveDate, NaiveDateTime, NaiveTime};
 use comrak::nodes::{AstNode, NodeValue};
+use serde::{Deserialize, Serialize};
 
 use crate::plan::util::get_node_text;
 
-#[derive(Debug, Clone)]
+/// How long the last event of a section lasts, since no event follows it.
+pub const LAST_EVENT_MINUTES: i64 = 30;
+
+/// A section of a [Schedule].
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
 pub enum ScheduleSection {
     Planned,
     Actual,
 }
 
-#[derive(Debug, Clone)]
+/// An event in a schedule, which lasts until the next event starts.
+#[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct Event {
     description: String,
     start: NaiveTime,
 }
 impl Event {
+    /// Initializes a new event.
+    pub fn new(start: NaiveTime, description: &str) -> Self {
+        Self {
+            description: description.to_string(),
+            start,
+        }
+    }
+
     /// Get a reference to the event's description.
     pub fn description(&self) -> &str {
         self.description.as_str()
@@ -26,13 +41,19 @@ impl Event {
     }
 }
 
-#[derive(Debug, Clone)]
+/// The planned and actual events of a plan.
+#[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct Schedule {
     planned: Vec<Event>,
     actual: Vec<Event>,
 }
 
 impl Schedule {
+    /// Initializes a new schedule.
+    pub fn new(planned: Vec<Event>, actual: Vec<Event>) -> Self {
+        Self { planned, actual }
+    }
+
     /// Get a reference to the planned events.
     pub fn planned(&self) -> &[Event] {
         self.planned.as_slice()
@@ -43,12 +64,130 @@ impl Schedule {
         self.actual.as_slice()
     }
 
+    /// Get a reference to the events of a section.
+    pub fn events(&self, section: ScheduleSection) -> &[Event] {
+        match section {
+            ScheduleSection::Planned => self.planned(),
+            ScheduleSection::Actual => self.actual(),
+        }
+    }
+
+    /// Pairs the events of a section with their end, which is the start of
+    /// the next event.  The last event has no end.
+    pub fn events_with_end(&self, section: ScheduleSection) -> Vec<(&Event, Option<NaiveTime>)> {
+        let events = self.events(section);
+
+        events
+            .iter()
+            .enumerate()
+            .map(|(i, event)| (event, events.get(i + 1).map(|next| next.start)))
+            .collect()
+    }
+
+    /// Places the events of a section on a plan's day, which runs from
+    /// `day_start` on `date` until `day_start` the next day.  Each event ends
+    /// when the next one starts, and the last one lasts
+    /// [LAST_EVENT_MINUTES], but not past the end of the day.
+    pub fn spans(
+        &self,
+        section: ScheduleSection,
+        date: NaiveDate,
+        day_start: NaiveTime,
+    ) -> Vec<Span<'_>> {
+        let at = |time| on_day(date, day_start, time);
+        let day_end = date.succ().and_time(day_start);
+
+        self.events_with_end(section)
+            .into_iter()
+            .map(|(event, end)| {
+                let start = at(event.start);
+                let end = match end.map(at) {
+                    Some(end) if end > start => end,
+                    _ => day_end.min(start + Duration::minutes(LAST_EVENT_MINUTES)),
+                };
+                Span { event, start, end }
+            })
+            .collect()
+    }
+
+    /// Finds the event of a section that is on at a time, and its index (see
+    /// [Schedule::spans]).
+    pub fn current(
+        &self,
+        section: ScheduleSection,
+        date: NaiveDate,
+        day_start: NaiveTime,
+        now: NaiveDateTime,
+    ) -> Option<(usize, Span<'_>)> {
+        self.spans(section, date, day_start)
+            .into_iter()
+            .enumerate()
+            .find(|(_, span)| span.start <= now && now < span.end)
+    }
+
+    /// Finds the first event of a section that starts after a time, and its
+    /// index (see [Schedule::spans]).
+    pub fn next(
+        &self,
+        section: ScheduleSection,
+        date: NaiveDate,
+        day_start: NaiveTime,
+        now: NaiveDateTime,
+    ) -> Option<(usize, Span<'_>)> {
+        self.spans(section, date, day_start)
+            .into_iter()
+            .enumerate()
+            .filter(|(_, span)| span.start > now)
+            .min_by_key(|(_, span)| span.start)
+    }
+
+    /// Get a mutable reference to the events of a section.
+    pub fn events_mut(&mut self, section: ScheduleSection) -> &mut Vec<Event> {
+        match section {
+            ScheduleSection::Planned => &mut self.planned,
+            ScheduleSection::Actual => &mut self.actual,
+        }
+    }
+
+    /// Adds an event to a section, keeping the section in order on a plan's
+    /// day, which runs from `day_start` on `date` (see [on_day]).
+    pub fn add_event(
+        &mut self,
+        section: ScheduleSection,
+        event: Event,
+        date: NaiveDate,
+        day_start: NaiveTime,
+    ) {
+        let at = |time| on_day(date, day_start, time);
+        let events = self.events_mut(section);
+        let index = events.partition_point(|e| at(e.start) <= at(event.start));
+        events.insert(index, event);
+    }
+
     /// Cleans the schedule, removing all actual events.
-    pub(super) fn clean(&mut self) {
+    pub fn clean(&mut self) {
         self.actual.clear();
     }
 }
 
+/// An event placed on a plan's day, with when it starts and ends.
+#[derive(Debug, Clone, Copy)]
+pub struct Span<'a> {
+    pub event: &'a Event,
+    pub start: NaiveDateTime,
+    pub end: NaiveDateTime,
+}
+
+/// Places a time on a plan's day, which runs from `day_start` on `date` until
+/// `day_start` the next day.
+pub fn on_day(date: NaiveDate, day_start: NaiveTime, time: NaiveTime) -> NaiveDateTime {
+    if time < day_start {
+        date.succ().and_time(time)
+    } else {
+        date.and_time(time)
+    }
+}
+
 fn parse_event<'a>(node: &'a AstNode<'a>) -> Option<Event> {
     log::debug!("Parsing event");
 
@@ -99,7 +238,7 @@ fn parse_event<'a>(node: &'a AstNode<'a>) -> Option<Event> {
     }
 }
 
-pub fn parse_schedule<'a>(node: &'a AstNode<'a>) -> anyhow::Result<Schedule> {
+pub(crate) fn parse_schedule<'a>(node: &'a AstNode<'a>) -> anyhow::Result<Schedule> {
     log::debug!("Parsing schedule...");
 
     let mut current_section: ScheduleSection = ScheduleSection::Planned;
@@ -156,3 +295,43 @@ pub fn parse_schedule<'a>(node: &'a AstNode<'a>) -> anyhow::Result<Schedule> {
 
     Ok(Schedule { planned, actual })
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn places_events_on_the_day() {
+        let schedule = Schedule::new(
+            vec![
+                Event::new(NaiveTime::from_hms(22, 0, 0), "Read"),
+                Event::new(NaiveTime::from_hms(23, 50, 0), "Sleep"),
+                Event::new(NaiveTime::from_hms(1, 0, 0), "Dream"),
+            ],
+            Vec::new(),
+        );
+        let date = NaiveDate::from_ymd(2021, 6, 1);
+        let next_day = date.succ();
+        let planned = ScheduleSection::Planned;
+
+        // Without a day start, the day ends at midnight, and 01:00 is early.
+        let midnight = NaiveTime::from_hms(0, 0, 0);
+        let spans = schedule.spans(planned, date, midnight);
+        assert_eq!(spans[1].end, next_day.and_hms(0, 0, 0));
+        assert_eq!(spans[2].end, date.and_hms(1, 30, 0));
+        let current = schedule.current(planned, date, midnight, date.and_hms(23, 55, 0));
+        assert_eq!(current.map(|(i, _)| i), Some(1));
+        let next = schedule.next(planned, date, midnight, date.and_hms(0, 30, 0));
+        assert_eq!(next.map(|(i, _)| i), Some(2));
+
+        // With a day start of 04:00, the day runs through the night.
+        let four = NaiveTime::from_hms(4, 0, 0);
+        let spans = schedule.spans(planned, date, four);
+        assert_eq!(spans[1].end, next_day.and_hms(1, 0, 0));
+        let current = schedule.current(planned, date, four, next_day.and_hms(1, 15, 0));
+        assert_eq!(current.map(|(i, _)| i), Some(2));
+        assert!(schedule
+            .current(planned, date, four, next_day.and_hms(3, 0, 0))
+            .is_none());
+    }
+}
@@ -1,17 +1,46 @@
-use comrak::nodes::{AstNode, NodeValue};
+use comrak::{
+    nodes::{AstNode, NodeValue},
+    parse_document, Arena, ComrakOptions,
+};
+use serde::{Deserialize, Serialize};
 
 use super::util::get_node_text;
 
-#[derive(Debug, Clone)]
+/// The tasks of a plan, grouped into categories.
+#[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct TaskList {
     categories: Vec<TaskCategory>,
 }
 impl TaskList {
+    /// Initializes a new task list.
+    pub fn new(categories: Vec<TaskCategory>) -> Self {
+        Self { categories }
+    }
+
     /// Get a reference to the tasks's categories.
     pub fn categories(&self) -> &[TaskCategory] {
         self.categories.as_slice()
     }
 
+    /// Get a mutable reference to the tasks's categories.
+    pub fn categories_mut(&mut self) -> &mut Vec<TaskCategory> {
+        &mut self.categories
+    }
+
+    /// Gets the category with the given name, adding an empty one if the list
+    /// doesn't have it yet.
+    pub fn category_mut(&mut self, name: &str) -> &mut TaskCategory {
+        let index = match self.categories.iter().position(|c| c.name == name) {
+            Some(i) => i,
+            None => {
+                self.categories.push(TaskCategory::new(name, Vec::new()));
+                self.categories.len() - 1
+            }
+        };
+
+        &mut self.categories[index]
+    }
+
     /// Cleans the [TaskList], removing all completed tasks.
     pub fn clean(&mut self) {
         for category in &mut self.categories {
@@ -20,18 +49,27 @@ impl TaskList {
     }
 }
 
-#[derive(Debug, Clone, PartialEq)]
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
 pub enum TaskStatus {
     Complete,
     Incomplete,
 }
 
-#[derive(Debug, Clone)]
+#[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct Task {
     description: String,
     status: TaskStatus,
 }
 impl Task {
+    /// Initializes a new task.
+    pub fn new(description: &str, status: TaskStatus) -> Self {
+        Self {
+            description: description.to_string(),
+            status,
+        }
+    }
+
     pub fn description(&self) -> &str {
         &self.description
     }
@@ -39,14 +77,73 @@ impl Task {
     pub fn status(&self) -> &TaskStatus {
         &self.status
     }
+
+    /// Gets the task's priority, written as `(A)` at the start of the
+    /// description.
+    pub fn priority(&self) -> Option<char> {
+        self.description
+            .split_whitespace()
+            .next()
+            .and_then(parse_priority)
+    }
+
+    /// Gets the description without the priority.
+    pub fn text(&self) -> &str {
+        match self.priority() {
+            Some(_) => self.description.trim_start()[3..].trim_start(),
+            None => &self.description,
+        }
+    }
+
+    /// Gets the task's tags, written as `#tag` in the description.
+    pub fn tags(&self) -> Vec<&str> {
+        self.text()
+            .split_whitespace()
+            .filter_map(|w| w.strip_prefix('#'))
+            .filter(|t| !t.is_empty())
+            .collect()
+    }
+
+    /// Gets the task's `key:value` pairs, e.g. `due:2021-06-01`.
+    pub fn metadata(&self) -> Vec<(&str, &str)> {
+        self.text()
+            .split_whitespace()
+            .filter_map(parse_key_value)
+            .collect()
+    }
+
+    /// Gets the value of a `key:value` pair.
+    pub fn get_metadata(&self, key: &str) -> Option<&str> {
+        self.metadata()
+            .into_iter()
+            .find(|(k, _)| *k == key)
+            .map(|(_, v)| v)
+    }
+
+    pub fn set_description(&mut self, description: &str) {
+        self.description = description.to_string();
+    }
+
+    pub fn set_status(&mut self, status: TaskStatus) {
+        self.status = status;
+    }
 }
 
-#[derive(Debug, Clone)]
+/// A named group of tasks, e.g. `Work`.
+#[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct TaskCategory {
     name: String,
     tasks: Vec<Task>,
 }
 impl TaskCategory {
+    /// Initializes a new task category.
+    pub fn new(name: &str, tasks: Vec<Task>) -> Self {
+        Self {
+            name: name.to_string(),
+            tasks,
+        }
+    }
+
     pub fn name(&self) -> &str {
         &self.name
     }
@@ -55,12 +152,43 @@ impl TaskCategory {
         &self.tasks
     }
 
+    pub fn tasks_mut(&mut self) -> &mut Vec<Task> {
+        &mut self.tasks
+    }
+
+    /// Removes all completed tasks from the category.
     pub fn clean(&mut self) {
         self.tasks.retain(|t| t.status == TaskStatus::Incomplete);
     }
 }
 
-pub fn parse_task_list<'a>(node: &'a AstNode<'a>) -> anyhow::Result<TaskList> {
+/// Parses a priority word like `(A)`.
+pub(crate) fn parse_priority(word: &str) -> Option<char> {
+    let mut chars = word.chars();
+
+    match (chars.next(), chars.next(), chars.next(), chars.next()) {
+        (Some('('), Some(p), Some(')'), None) if p.is_ascii_uppercase() => Some(p),
+        _ => None,
+    }
+}
+
+/// Parses a `key:value` word.  URLs like `https://example.com` are not
+/// `key:value` pairs.
+pub(crate) fn parse_key_value(word: &str) -> Option<(&str, &str)> {
+    let (key, value) = word.split_once(':')?;
+    let is_key = !key.is_empty()
+        && key
+            .chars()
+            .all(|c| c.is_ascii_alphanumeric() || c == '-' || c == '_');
+
+    if is_key && !value.is_empty() && !value.starts_with('/') {
+        Some((key, value))
+    } else {
+        None
+    }
+}
+
+pub(crate) fn parse_task_list<'a>(node: &'a AstNode<'a>) -> anyhow::Result<TaskList> {
     let mut categories: Vec<TaskCategory> = Vec::new();
 
     for child in node.children() {
@@ -80,21 +208,7 @@ fn parse_task_category<'a>(node: &'a AstNode<'a>) -> anyhow::Result<TaskCategory
             NodeValue::List(_) => {
                 for node in node.children() {
                     if let NodeValue::Item(_) = &node.data.borrow().value {
-                        let text = get_node_text(node);
-                        let status = if text.starts_with("[x]") {
-                            TaskStatus::Complete
-                        } else {
-                            TaskStatus::Incomplete
-                        };
-
-                        tasks.push(Task {
-                            description: text
-                                .trim_start_matches("[ ]")
-                                .trim_start_matches("[x]")
-                                .trim()
-                                .to_string(),
-                            status,
-                        });
+                        tasks.push(parse_task(&get_node_text(node)));
                     }
                 }
             }
@@ -104,3 +218,58 @@ fn parse_task_category<'a>(node: &'a AstNode<'a>) -> anyhow::Result<TaskCategory
 
     Ok(TaskCategory { name, tasks })
 }
+
+/// Parses a task from the text of a list item, like `[x] Call the bank`.
+fn parse_task(text: &str) -> Task {
+    let (status, description) = match text.get(..3) {
+        Some("[x]") | Some("[X]") => (TaskStatus::Complete, &text[3..]),
+        Some("[ ]") => (TaskStatus::Incomplete, &text[3..]),
+        _ => (TaskStatus::Incomplete, text),
+    };
+
+    Task {
+        description: description.trim().to_string(),
+        status,
+    }
+}
+
+/// Parses a task from a single list item line of a document, like
+/// `  - [x] Call **the bank**`, the same way as tasks in a task list.
+pub(crate) fn parse_task_line(line: &str) -> Option<Task> {
+    let arena = Arena::new();
+    let root = parse_document(&arena, line.trim_start(), &ComrakOptions::default());
+    let item = root.first_child()?.first_child()?;
+
+    let is_item = matches!(item.data.borrow().value, NodeValue::Item(_));
+    if is_item {
+        Some(parse_task(&get_node_text(item)))
+    } else {
+        None
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn task_metadata() {
+        let task = Task::new(
+            "(A) Call the bank #phone due:2021-06-02 about https://example.com",
+            TaskStatus::Incomplete,
+        );
+
+        assert_eq!(task.priority(), Some('A'));
+        assert_eq!(
+            task.text(),
+            "Call the bank #phone due:2021-06-02 about https://example.com"
+        );
+        assert_eq!(task.tags(), vec!["phone"]);
+        assert_eq!(task.metadata(), vec![("due", "2021-06-02")]);
+        assert_eq!(task.get_metadata("due"), Some("2021-06-02"));
+
+        let task = Task::new("(a) lowercase is not a priority", TaskStatus::Incomplete);
+        assert_eq!(task.priority(), None);
+        assert_eq!(task.text(), task.description());
+    }
+}
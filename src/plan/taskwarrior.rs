@@ -0,0 +1,231 @@
+//! Conversion between task lists and [Taskwarrior](https://taskwarrior.org)'s
+//! JSON import/export format.
+//!
+//! Task categories become projects, `#tags` become tags, `due:YYYY-MM-DD`
+//! becomes the due date, and the priorities `(A)`, `(B)` and `(C)` become `H`,
+//! `M` and `L`.  A task's Taskwarrior UUID is kept in its description as
+//! `uuid:...`, so that syncing again updates the task instead of adding it
+//! twice.
+
+use chrono::{DateTime, Local, NaiveDate, NaiveDateTime, TimeZone, Utc};
+use serde::{Deserialize, Serialize};
+
+use super::tasks::{parse_key_value, Task, TaskList, TaskStatus};
+
+/// The format of Taskwarrior's dates, which are always in UTC.
+const DATE_FORMAT: &str = "%Y%m%dT%H%M%SZ";
+
+const PRIORITIES: &[(char, &str)] = &[('A', "H"), ('B', "M"), ('C', "L")];
+
+/// A task as Taskwarrior imports and exports it.  Attributes planner doesn't
+/// use are left out.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct TaskwarriorTask {
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub uuid: Option<String>,
+    pub description: String,
+    pub status: TaskwarriorStatus,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub project: Option<String>,
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub tags: Vec<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub due: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub priority: Option<String>,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum TaskwarriorStatus {
+    Pending,
+    Completed,
+    Deleted,
+    Waiting,
+    Recurring,
+}
+
+/// Exports the open tasks of a task list, and the completed tasks that came
+/// from Taskwarrior, so that they're completed there too.
+pub fn to_taskwarrior(tasks: &TaskList) -> Vec<TaskwarriorTask> {
+    let mut exported = Vec::new();
+
+    for category in tasks.categories() {
+        for task in category.tasks() {
+            if task.status() == &TaskStatus::Incomplete || task.get_metadata("uuid").is_some() {
+                exported.push(to_taskwarrior_task(category.name(), task));
+            }
+        }
+    }
+
+    exported
+}
+
+fn to_taskwarrior_task(category: &str, task: &Task) -> TaskwarriorTask {
+    let mut description = Vec::new();
+    let mut tags = Vec::new();
+    let mut due = None;
+    let mut uuid = None;
+
+    for word in task.text().split_whitespace() {
+        match (word.strip_prefix('#'), parse_key_value(word)) {
+            (Some(tag), _) if !tag.is_empty() => tags.push(tag.to_string()),
+            (_, Some(("uuid", value))) => uuid = Some(value.to_string()),
+            (_, Some(("due", value))) if format_due(value).is_some() => due = format_due(value),
+            _ => description.push(word),
+        }
+    }
+
+    let priority = task.priority().and_then(|p| {
+        PRIORITIES
+            .iter()
+            .find(|(letter, _)| *letter == p)
+            .map(|(_, level)| level.to_string())
+    });
+
+    TaskwarriorTask {
+        uuid,
+        description: description.join(" "),
+        status: match task.status() {
+            TaskStatus::Complete => TaskwarriorStatus::Completed,
+            TaskStatus::Incomplete => TaskwarriorStatus::Pending,
+        },
+        project: Some(category.to_string()),
+        tags,
+        due,
+        priority,
+    }
+}
+
+/// Imports a Taskwarrior task, returning its project (if it has one) and the
+/// task.
+pub fn from_taskwarrior(task: &TaskwarriorTask) -> (Option<&str>, Task) {
+    let mut words = Vec::new();
+
+    let priority = task
+        .priority
+        .as_deref()
+        .and_then(|level| PRIORITIES.iter().find(|(_, l)| *l == level));
+    if let Some((letter, _)) = priority {
+        words.push(format!("({})", letter));
+    }
+
+    words.push(task.description.trim().to_string());
+    words.extend(task.tags.iter().map(|t| format!("#{}", t)));
+    if let Some(date) = task.due.as_deref().and_then(parse_due) {
+        words.push(format!("due:{}", date.format("%Y-%m-%d")));
+    }
+    if let Some(uuid) = &task.uuid {
+        words.push(format!("uuid:{}", uuid));
+    }
+
+    let status = match task.status {
+        TaskwarriorStatus::Completed => TaskStatus::Complete,
+        _ => TaskStatus::Incomplete,
+    };
+
+    (task.project.as_deref(), Task::new(&words.join(" "), status))
+}
+
+/// Parses the output of `task export`: a JSON array, or one task per line
+/// from older versions of Taskwarrior.
+pub fn parse(json: &str) -> anyhow::Result<Vec<TaskwarriorTask>> {
+    if json.trim_start().starts_with('[') {
+        return Ok(serde_json::from_str(json)?);
+    }
+
+    json.lines()
+        .map(|l| l.trim().trim_end_matches(','))
+        .filter(|l| !l.is_empty())
+        .map(|l| Ok(serde_json::from_str(l)?))
+        .collect()
+}
+
+/// Converts a `due:` date into a Taskwarrior date at local midnight.
+fn format_due(date: &str) -> Option<String> {
+    let midnight = NaiveDate::parse_from_str(date, "%Y-%m-%d")
+        .ok()?
+        .and_hms(0, 0, 0);
+    let local = Local.from_local_datetime(&midnight).earliest()?;
+
+    Some(local.with_timezone(&Utc).format(DATE_FORMAT).to_string())
+}
+
+/// Gets the local date of a Taskwarrior date.
+fn parse_due(due: &str) -> Option<NaiveDate> {
+    let utc = NaiveDateTime::parse_from_str(due, DATE_FORMAT).ok()?;
+
+    Some(
+        DateTime::<Utc>::from_utc(utc, Utc)
+            .with_timezone(&Local)
+            .date()
+            .naive_local(),
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::plan::tasks::TaskCategory;
+
+    #[test]
+    fn round_trip() {
+        let tasks = TaskList::new(vec![TaskCategory::new(
+            "Work",
+            vec![
+                Task::new(
+                    "(A) Call the bank #phone due:2021-06-02 uuid:5f1c6a4e-0d6f-4f3b-9d1e-2b7f3c8e9a10",
+                    TaskStatus::Incomplete,
+                ),
+                Task::new("Write report", TaskStatus::Complete),
+                Task::new("Review pull requests", TaskStatus::Incomplete),
+                Task::new("Pay rent uuid:0b5e0c1a", TaskStatus::Complete),
+            ],
+        )]);
+
+        let exported = to_taskwarrior(&tasks);
+        assert_eq!(exported.len(), 3);
+        assert_eq!(exported[0].description, "Call the bank");
+        assert_eq!(exported[0].project.as_deref(), Some("Work"));
+        assert_eq!(exported[0].tags, vec!["phone"]);
+        assert_eq!(exported[0].priority.as_deref(), Some("H"));
+        assert_eq!(
+            exported[0].uuid.as_deref(),
+            Some("5f1c6a4e-0d6f-4f3b-9d1e-2b7f3c8e9a10")
+        );
+
+        let (project, task) = from_taskwarrior(&exported[0]);
+        assert_eq!(project, Some("Work"));
+        assert_eq!(
+            task.description(),
+            tasks.categories()[0].tasks()[0].description()
+        );
+
+        let (_, task) = from_taskwarrior(&exported[1]);
+        assert_eq!(task.description(), "Review pull requests");
+
+        assert_eq!(exported[2].status, TaskwarriorStatus::Completed);
+        assert_eq!(exported[2].uuid.as_deref(), Some("0b5e0c1a"));
+    }
+
+    #[test]
+    fn parses_task_export() {
+        let json = r#"[
+            {"id":0,"description":"Pay rent","end":"20210601T101500Z","entry":"20210530T080000Z","modified":"20210601T101500Z","project":"Home","status":"completed","uuid":"0b5e0c1a-7a55-4c2e-8f0e-3d2c1b4a5e6f","urgency":1.9},
+            {"id":1,"description":"Buy milk","due":"20210602T120000Z","entry":"20210530T080000Z","priority":"L","status":"pending","tags":["errands"],"uuid":"9c8b7a6d-5e4f-4a3b-8c2d-1e0f9a8b7c6d","urgency":4.2}
+        ]"#;
+
+        let tasks = parse(json).unwrap();
+        assert_eq!(tasks[0].status, TaskwarriorStatus::Completed);
+        assert_eq!(tasks[1].project, None);
+
+        let (_, task) = from_taskwarrior(&tasks[1]);
+        assert_eq!(
+            task.description(),
+            "(C) Buy milk #errands due:2021-06-02 uuid:9c8b7a6d-5e4f-4a3b-8c2d-1e0f9a8b7c6d"
+        );
+
+        let lines = json.lines().skip(1).take(2).collect::<Vec<_>>().join("\n");
+        assert_eq!(parse(&lines).unwrap(), tasks);
+    }
+}
@@ -0,0 +1,181 @@
+//! Conversion between task lists and the [todo.txt](http://todotxt.org)
+//! format.
+//!
+//! Task categories become `+project`s, `#tags` become `@context`s, and
+//! completed tasks are marked `x` with the plan date as the completion date.
+//! Priorities and `key:value` pairs are kept as they are, except that the
+//! priority of a completed task is kept as `pri:A`.
+
+use chrono::NaiveDate;
+
+use super::tasks::{parse_priority, Task, TaskList, TaskStatus};
+
+/// The category of tasks without a `+project`.
+pub const DEFAULT_CATEGORY: &str = "Inbox";
+
+/// Exports a task list as todo.txt, one line per task.
+pub fn to_todotxt(tasks: &TaskList, date: NaiveDate) -> String {
+    let mut todotxt = String::new();
+
+    for category in tasks.categories() {
+        let project = format!(
+            "+{}",
+            category
+                .name()
+                .split_whitespace()
+                .collect::<Vec<_>>()
+                .join("_")
+        );
+
+        for task in category.tasks() {
+            let mut words: Vec<String> = Vec::new();
+
+            match (task.status(), task.priority()) {
+                (TaskStatus::Complete, _) => words.push(format!("x {}", date.format("%Y-%m-%d"))),
+                (TaskStatus::Incomplete, Some(p)) => words.push(format!("({})", p)),
+                (TaskStatus::Incomplete, None) => {}
+            }
+
+            words.extend(
+                task.text()
+                    .split_whitespace()
+                    .map(|w| match w.strip_prefix('#') {
+                        Some(tag) if !tag.is_empty() => format!("@{}", tag),
+                        _ => w.to_string(),
+                    }),
+            );
+            words.push(project.clone());
+
+            if let (TaskStatus::Complete, Some(p)) = (task.status(), task.priority()) {
+                words.push(format!("pri:{}", p));
+            }
+
+            todotxt.push_str(&words.join(" "));
+            todotxt.push('\n');
+        }
+    }
+
+    todotxt
+}
+
+/// Imports todo.txt tasks, grouping them into categories by their first
+/// `+project`.
+pub fn from_todotxt(todotxt: &str) -> TaskList {
+    let mut tasks = TaskList::new(Vec::new());
+
+    for line in todotxt.lines().filter(|l| !l.trim().is_empty()) {
+        let (category, task) = parse_line(line);
+        tasks.category_mut(&category).tasks_mut().push(task);
+    }
+
+    tasks
+}
+
+fn parse_line(line: &str) -> (String, Task) {
+    let mut words = line.split_whitespace().peekable();
+    let mut status = TaskStatus::Incomplete;
+    let mut priority = None;
+
+    if words.peek() == Some(&"x") {
+        words.next();
+        status = TaskStatus::Complete;
+    } else if let Some(p) = words.peek().and_then(|w| parse_priority(w)) {
+        words.next();
+        priority = Some(p);
+    }
+
+    // Skip the completion and creation dates.
+    while words.next_if(|w| is_date(w)).is_some() {}
+
+    let mut project = None;
+    let mut text = Vec::new();
+    for word in words {
+        if let Some(name) = word.strip_prefix('+').filter(|n| !n.is_empty()) {
+            if project.is_none() {
+                project = Some(name.replace('_', " "));
+                continue;
+            }
+        }
+
+        match (word.strip_prefix('@'), word.strip_prefix("pri:")) {
+            (Some(context), _) if !context.is_empty() => text.push(format!("#{}", context)),
+            (_, Some(p))
+                if status == TaskStatus::Complete
+                    && parse_priority(&format!("({})", p)).is_some() =>
+            {
+                priority = p.chars().next();
+            }
+            _ => text.push(word.to_string()),
+        }
+    }
+
+    let mut description = text.join(" ");
+    if let Some(p) = priority {
+        description = format!("({}) {}", p, description);
+    }
+
+    (
+        project.unwrap_or_else(|| DEFAULT_CATEGORY.to_string()),
+        Task::new(&description, status),
+    )
+}
+
+fn is_date(word: &str) -> bool {
+    NaiveDate::parse_from_str(word, "%Y-%m-%d").is_ok()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::plan::tasks::TaskCategory;
+    use indoc::indoc;
+
+    #[test]
+    fn round_trip() {
+        let tasks = TaskList::new(vec![
+            TaskCategory::new(
+                "Work",
+                vec![
+                    Task::new(
+                        "(A) Call the bank #phone due:2021-06-02",
+                        TaskStatus::Incomplete,
+                    ),
+                    Task::new("(B) Write report", TaskStatus::Complete),
+                ],
+            ),
+            TaskCategory::new(
+                "Side Projects",
+                vec![Task::new("Fix the website", TaskStatus::Incomplete)],
+            ),
+        ]);
+        let date = NaiveDate::from_ymd(2021, 6, 1);
+
+        let todotxt = to_todotxt(&tasks, date);
+        assert_eq!(
+            todotxt,
+            indoc! {"
+                (A) Call the bank @phone due:2021-06-02 +Work
+                x 2021-06-01 Write report +Work pri:B
+                Fix the website +Side_Projects
+            "}
+        );
+        assert_eq!(to_todotxt(&from_todotxt(&todotxt), date), todotxt);
+    }
+
+    #[test]
+    fn imports_todotxt() {
+        let tasks = from_todotxt(indoc! {"
+            (C) 2021-05-30 Buy milk @errands +Home
+            x 2021-06-01 2021-05-30 Pay rent +Home
+            Read a book
+        "});
+
+        let names: Vec<&str> = tasks.categories().iter().map(|c| c.name()).collect();
+        assert_eq!(names, vec!["Home", DEFAULT_CATEGORY]);
+
+        let home = &tasks.categories()[0].tasks();
+        assert_eq!(home[0].description(), "(C) Buy milk #errands");
+        assert_eq!(home[1].description(), "Pay rent");
+        assert_eq!(home[1].status(), &TaskStatus::Complete);
+    }
+}
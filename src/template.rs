@@ -0,0 +1,49 @@
+use chrono::NaiveDate;
+
+/// The name of the template file in the plan directory.
+pub const TEMPLATE_FILE: &str = "template.md";
+
+/// The template used when the plan directory doesn't have one.
+pub const STARTER_TEMPLATE: &str = "# {{date}}
+{{day}}
+
+## Tasks
+- **Work**
+  - [ ] Review yesterday's plan
+- **Personal**
+  - [ ] Take a walk
+
+## Schedule
+- **Planned**
+  - 0900\tStart the day
+  - 1200\tLunch
+- **Actual**
+
+## Notes
+
+";
+
+/// Renders a plan template for the given date.
+///
+/// `{{date}}` is replaced with the header date and `{{day}}` with the weekday.
+pub fn render(template: &str, date: NaiveDate) -> String {
+    template
+        .replace("{{date}}", &date.format("%m/%d/%Y").to_string())
+        .replace("{{day}}", &date.format("%A").to_string())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::plan::Plan;
+
+    #[test]
+    fn starter_template_parses() {
+        let date = NaiveDate::from_ymd(2021, 6, 1);
+        let plan = Plan::from_markdown(&render(STARTER_TEMPLATE, date)).unwrap();
+
+        assert_eq!(plan.date(), date);
+        assert_eq!(plan.tasks().as_ref().unwrap().categories().len(), 2);
+        assert_eq!(plan.schedule().as_ref().unwrap().planned().len(), 2);
+    }
+}